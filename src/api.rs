@@ -1,3 +1,5 @@
+#[cfg(feature = "extra-fields")]
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 
 /// Collections/entries request
@@ -37,6 +39,9 @@ pub struct EntriesRequest {
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct EntriesResponse {
     pub objects: EntryObjects,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -44,6 +49,9 @@ pub struct EntryObjects {
     pub timelines: HashMap<String, Timeline>,
     pub tweets: HashMap<String, Tweet>,
     pub response: EntryObjectsResponse,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -56,17 +64,25 @@ pub struct Timeline {
     pub url: String,
     pub user_id: String,
     pub visibility: String,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Tweet {
     pub contributors: Option<Vec<Contributor>>,
-    pub coordinates: Option<Vec<Coordinate>>,
+    pub coordinates: Option<Coordinate>,
     pub created_at: String, //@sg actually this is date/time
     pub entities: TweetEntities,
-    pub extended_entities: TweetExtendedEntities,
+    /// Only present when the tweet has native media attached.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extended_entities: Option<TweetExtendedEntities>,
     pub favorite_count: u64,
     pub favorited: bool,
+    /// The text in `tweet_mode=extended`, which sends it instead of `text`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub full_text: Option<String>,
     pub geo: Option<Geo>,
     pub id: u64,
     pub id_str: String,
@@ -75,56 +91,135 @@ pub struct Tweet {
     pub in_reply_to_status_id_str: Option<String>,
     pub in_reply_to_user_id: Option<u64>,
     pub in_reply_to_user_id_str: Option<String>,
-    pub is_quote_status: bool,
-    pub lang: String,
+    /// Missing on tweets created before quote tweets were introduced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_quote_status: Option<bool>,
+    /// `None` when Twitter could not detect a language.
+    pub lang: Option<String>,
+    /// The rules a tweet from Enterprise search or filtered streams matched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matching_rules: Option<Vec<MatchingRule>>,
     pub place: Option<Place>,
-    pub possibly_sensitive: bool,
+    /// Only present when the tweet contains a link.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub possibly_sensitive: Option<bool>,
+    /// Missing when the quoted tweet was deleted or its author is protected,
+    /// even though `quoted_status_id` is still set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quoted_status: Option<Box<Tweet>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quoted_status_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quoted_status_id_str: Option<String>,
     pub retweet_count: u64,
     pub retweeted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retweeted_status: Option<Box<Tweet>>,
     pub source: String,
-    pub text: String,
+    /// Missing in `tweet_mode=extended`, see [`Tweet::body`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
     pub truncated: bool,
     pub user: User,
+    /// Fields Twitter sent that are not modelled above, kept so
+    /// re-serializing reproduces the original payload. Every model has one
+    /// with the `extra-fields` feature.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Tweet {
+    /// The text of the tweet, whichever of `text` and `full_text` it came in.
+    pub fn body(&self) -> &str {
+        self.full_text
+            .as_deref()
+            .or(self.text.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+/// A rule of a search or stream that a tweet matched.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct MatchingRule {
+    /// Label given to the rule when it was added.
+    pub tag: Option<String>,
+    pub id: u64,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Contributor {
     //@sg todo
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
+/// GeoJSON point, `[longitude, latitude]`.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Coordinate {
-    //@sg todo
+    pub coordinates: Vec<f64>,
+    #[serde(rename = "type")]
+    pub coordinate_type: String,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct TweetEntities {
-    pub hashtags: Vec<String>,
+    #[serde(default)]
+    pub hashtags: Vec<Hashtag>,
+    /// Only present when the tweet has native media attached.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub media: Vec<Media>,
-    pub symbols: Vec<String>,
-    pub urls: Vec<String>,
+    /// Missing on tweets created before cashtags were introduced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbols: Option<Vec<Symbol>>,
+    #[serde(default)]
+    pub urls: Vec<Url>,
+    #[serde(default)]
     pub user_mentions: Vec<UserMention>,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct TweetExtendedEntities {
     pub media: Vec<Media>,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Geo {
     //@sg todo
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Place {
     //@sg todo
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Media {
     pub display_url: String,
     pub expanded_url: String,
+    /// Alt text from `media/metadata/create`, only sent in
+    /// `extended_entities` when asked for with `include_ext_alt_text`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ext_alt_text: Option<String>,
     pub id: u64,
     pub id_str: String,
     pub indices: Vec<u64>,
@@ -134,6 +229,42 @@ pub struct Media {
     #[serde(rename = "type")]
     pub media_type: String,
     pub url: String,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Hashtag {
+    pub indices: Vec<u64>,
+    pub text: String,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Cashtag such as `$TWTR`, `text` is stored without the `$`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Symbol {
+    pub indices: Vec<u64>,
+    pub text: String,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Url {
+    /// Missing on some tweets created before link wrapping was introduced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_url: Option<String>,
+    /// `None` on some tweets created before link wrapping was introduced.
+    pub expanded_url: Option<String>,
+    pub indices: Vec<u64>,
+    pub url: String,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -143,12 +274,21 @@ pub struct UserMention {
     pub indices: Vec<u64>,
     pub name: String,
     pub screen_name: String,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct User {
     pub id: u64,
     pub id_str: String, //@sg not sure we need to keep that
+    /// Missing when the tweet was fetched with `trim_user`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen_name: Option<String>,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -156,6 +296,9 @@ pub struct Size {
     pub h: u16,
     pub w: u16,
     pub resize: String,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -164,6 +307,9 @@ pub struct Sizes {
     pub medium: Size,
     pub small: Size,
     pub thumb: Size,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -171,18 +317,27 @@ pub struct EntryObjectsResponse {
     pub position: EntryObjectsResponsePosition,
     pub timeline: Vec<EntryObjectsResponseTimeline>,
     pub timeline_id: String,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct EntryObjectsResponseTimeline {
     pub feature_context: String,
     pub tweet: TweetReference,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct TweetReference {
     pub id: String,
     pub sort_index: String,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -190,11 +345,119 @@ pub struct EntryObjectsResponsePosition {
     pub max_position: String,
     pub min_position: String,
     pub was_truncated: bool,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn fixture(name: &str) -> Tweet {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/tweets")
+            .join(name);
+        let json = fs::read_to_string(&path).unwrap();
+        serde_json::from_str(&json).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+    }
+
+    #[test]
+    fn parse_tweet_fixtures() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tweets");
+        let mut parsed = 0;
+        for entry in fs::read_dir(dir).unwrap() {
+            let name = entry.unwrap().file_name();
+            fixture(name.to_str().unwrap());
+            parsed += 1;
+        }
+        assert!(parsed > 0);
+    }
+
+    #[test]
+    fn parse_tweet_without_media() {
+        let tweet = fixture("plain_text.json");
+        assert_eq!(tweet.extended_entities, None);
+        assert_eq!(tweet.possibly_sensitive, None);
+        assert!(tweet.entities.media.is_empty());
+    }
+
+    #[test]
+    fn parse_alt_text() {
+        let tweet = fixture("photo_with_alt_text.json");
+        assert_eq!(tweet.entities.media[0].ext_alt_text, None);
+        let media = &tweet.extended_entities.unwrap().media[0];
+        assert_eq!(media.media_type, "photo");
+        assert_eq!(
+            media.ext_alt_text.as_ref().unwrap(),
+            "An orange sun setting behind hills across a calm bay."
+        );
+    }
+
+    #[test]
+    fn parse_retweet() {
+        let tweet = fixture("retweet.json");
+        let original = tweet.retweeted_status.unwrap();
+        assert_eq!(original.id, 1166013785106300928);
+        assert_eq!(original.user.id, 2244994945);
+    }
+
+    #[test]
+    fn parse_quote_of_deleted_tweet() {
+        let tweet = fixture("quote_of_deleted_tweet.json");
+        assert_eq!(tweet.is_quote_status, Some(true));
+        assert_eq!(tweet.quoted_status_id, Some(1084906538462851072));
+        assert_eq!(tweet.quoted_status, None);
+    }
+
+    #[test]
+    fn parse_legacy_tweet() {
+        let tweet = fixture("reply_to_deleted_user.json");
+        assert_eq!(tweet.is_quote_status, None);
+        assert_eq!(tweet.entities.symbols, None);
+        assert_eq!(tweet.lang, None);
+        assert_eq!(
+            tweet.in_reply_to_screen_name.as_ref().unwrap(),
+            "gone_account"
+        );
+
+        let tweet = fixture("legacy_url_entity.json");
+        assert_eq!(tweet.entities.urls[0].expanded_url, None);
+        assert_eq!(tweet.entities.urls[0].display_url, None);
+    }
+
+    #[test]
+    fn parse_extended_mode() {
+        let tweet = fixture("extended_mode.json");
+        assert_eq!(tweet.text, None);
+        assert!(tweet.body().len() > 140);
+        assert_eq!(tweet.body(), tweet.full_text.as_deref().unwrap());
+
+        let tweet = fixture("plain_text.json");
+        assert_eq!(tweet.full_text, None);
+        assert_eq!(tweet.body(), tweet.text.as_deref().unwrap());
+    }
+
+    #[cfg(feature = "extra-fields")]
+    #[test]
+    fn round_trip_unknown_fields() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tweets");
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_str().unwrap();
+            let tweet = fixture(name);
+            let original: Value =
+                serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            assert_eq!(serde_json::to_value(&tweet).unwrap(), original, "{}", name);
+        }
+
+        let tweet = fixture("plain_text.json");
+        assert_eq!(tweet.user.screen_name.as_ref().unwrap(), "TwitterDev");
+        assert_eq!(tweet.user.extra["name"], "Twitter Dev");
+        assert!(tweet.extra.is_empty());
+    }
 
     #[test]
     fn parse_collections_entries() {
@@ -389,6 +652,8 @@ mod tests {
                         url: "".to_string(),
                         user_id: "2244994945".to_string(),
                         visibility: "public".to_string(),
+                        #[cfg(feature = "extra-fields")]
+                        extra: serde_json::Map::new(),
                     },
                 )]
                 .iter()
@@ -407,6 +672,7 @@ mod tests {
                                 expanded_url:
                                     "http://twitter.com/Interior/status/504032379045179393/photo/1"
                                         .to_string(),
+                                ext_alt_text: None,
                                 id: 504032378411446273,
                                 id_str: "504032378411446273".to_string(),
                                 indices: vec![99, 121],
@@ -419,27 +685,39 @@ mod tests {
                                         h: 695,
                                         resize: "fit".to_string(),
                                         w: 1024,
+                                        #[cfg(feature = "extra-fields")]
+                                        extra: serde_json::Map::new(),
                                     },
                                     medium: Size {
                                         h: 407,
                                         resize: "fit".to_string(),
                                         w: 600,
+                                        #[cfg(feature = "extra-fields")]
+                                        extra: serde_json::Map::new(),
                                     },
                                     small: Size {
                                         h: 230,
                                         resize: "fit".to_string(),
                                         w: 340,
+                                        #[cfg(feature = "extra-fields")]
+                                        extra: serde_json::Map::new(),
                                     },
                                     thumb: Size {
                                         h: 150,
                                         resize: "crop".to_string(),
                                         w: 150,
+                                        #[cfg(feature = "extra-fields")]
+                                        extra: serde_json::Map::new(),
                                     },
+                                    #[cfg(feature = "extra-fields")]
+                                    extra: serde_json::Map::new(),
                                 },
                                 media_type: "photo".to_string(),
                                 url: "http://t.co/HtdvV0bPEu".to_string(),
+                                #[cfg(feature = "extra-fields")]
+                                extra: serde_json::Map::new(),
                             }],
-                            symbols: Vec::new(),
+                            symbols: Some(Vec::new()),
                             urls: Vec::new(),
                             user_mentions: vec![UserMention {
                                 id: 66453289,
@@ -447,14 +725,19 @@ mod tests {
                                 indices: vec![47, 60],
                                 name: "Lake Clark NP&P".to_string(),
                                 screen_name: "LakeClarkNPS".to_string(),
+                                #[cfg(feature = "extra-fields")]
+                                extra: serde_json::Map::new(),
                             }],
+                            #[cfg(feature = "extra-fields")]
+                            extra: serde_json::Map::new(),
                         },
-                        extended_entities: TweetExtendedEntities {
+                        extended_entities: Some(TweetExtendedEntities {
                             media: vec![Media {
                                 display_url: "pic.twitter.com/HtdvV0bPEu".to_string(),
                                 expanded_url:
                                     "http://twitter.com/Interior/status/504032379045179393/photo/1"
                                         .to_string(),
+                                ext_alt_text: None,
                                 id: 504032378411446273,
                                 id_str: "504032378411446273".to_string(),
                                 indices: vec![99, 121],
@@ -467,29 +750,44 @@ mod tests {
                                         h: 695,
                                         resize: "fit".to_string(),
                                         w: 1024,
+                                        #[cfg(feature = "extra-fields")]
+                                        extra: serde_json::Map::new(),
                                     },
                                     medium: Size {
                                         h: 407,
                                         resize: "fit".to_string(),
                                         w: 600,
+                                        #[cfg(feature = "extra-fields")]
+                                        extra: serde_json::Map::new(),
                                     },
                                     small: Size {
                                         h: 230,
                                         resize: "fit".to_string(),
                                         w: 340,
+                                        #[cfg(feature = "extra-fields")]
+                                        extra: serde_json::Map::new(),
                                     },
                                     thumb: Size {
                                         h: 150,
                                         resize: "crop".to_string(),
                                         w: 150,
+                                        #[cfg(feature = "extra-fields")]
+                                        extra: serde_json::Map::new(),
                                     },
+                                    #[cfg(feature = "extra-fields")]
+                                    extra: serde_json::Map::new(),
                                 },
                                 media_type: "photo".to_string(),
                                 url: "http://t.co/HtdvV0bPEu".to_string(),
+                                #[cfg(feature = "extra-fields")]
+                                extra: serde_json::Map::new(),
                             }],
-                        },
+                            #[cfg(feature = "extra-fields")]
+                            extra: serde_json::Map::new(),
+                        }),
                         favorite_count: 639,
                         favorited: false,
+                        full_text: None,
                         geo: None,
                         id: 504032379045179393,
                         id_str: "504032379045179393".to_string(),
@@ -498,21 +796,33 @@ mod tests {
                         in_reply_to_status_id_str: None,
                         in_reply_to_user_id: None,
                         in_reply_to_user_id_str: None,
-                        is_quote_status: false,
-                        lang: "en".to_string(),
+                        is_quote_status: Some(false),
+                        lang: Some("en".to_string()),
+                        matching_rules: None,
                         place: None,
-                        possibly_sensitive: false,
+                        possibly_sensitive: Some(false),
+                        quoted_status: None,
+                        quoted_status_id: None,
+                        quoted_status_id_str: None,
                         retweet_count: 606,
                         retweeted: false,
+                        retweeted_status: None,
                         source: "Twitter for iPhone".to_string(),
-                        text: "How about a grizzly bear waving for the camera @LakeClarkNPS to \
-                               end the day? Photo: Kevin Dietrich http://t.co/HtdvV0bPEu"
-                            .to_string(),
+                        text: Some(
+                            "How about a grizzly bear waving for the camera @LakeClarkNPS to \
+                             end the day? Photo: Kevin Dietrich http://t.co/HtdvV0bPEu"
+                                .to_string(),
+                        ),
                         truncated: false,
                         user: User {
                             id: 76348185,
                             id_str: "76348185".to_string(),
+                            screen_name: None,
+                            #[cfg(feature = "extra-fields")]
+                            extra: serde_json::Map::new(),
                         },
+                        #[cfg(feature = "extra-fields")]
+                        extra: serde_json::Map::new(),
                     },
                 )]
                 .iter()
@@ -523,6 +833,8 @@ mod tests {
                         max_position: "371578415352947200".to_string(),
                         min_position: "371578380871797248".to_string(),
                         was_truncated: false,
+                        #[cfg(feature = "extra-fields")]
+                        extra: serde_json::Map::new(),
                     },
                     timeline: vec![
                         EntryObjectsResponseTimeline {
@@ -530,26 +842,44 @@ mod tests {
                             tweet: TweetReference {
                                 id: "504032379045179393".to_string(),
                                 sort_index: "371578415352947200".to_string(),
+                                #[cfg(feature = "extra-fields")]
+                                extra: serde_json::Map::new(),
                             },
+                            #[cfg(feature = "extra-fields")]
+                            extra: serde_json::Map::new(),
                         },
                         EntryObjectsResponseTimeline {
                             feature_context: "HBgGY3VzdG9tFoCAktzo1NL8DgAA".to_string(),
                             tweet: TweetReference {
                                 id: "532654992071852032".to_string(),
                                 sort_index: "371578393139797760".to_string(),
+                                #[cfg(feature = "extra-fields")]
+                                extra: serde_json::Map::new(),
                             },
+                            #[cfg(feature = "extra-fields")]
+                            extra: serde_json::Map::new(),
                         },
                         EntryObjectsResponseTimeline {
                             feature_context: "HBgGY3VzdG9tFoCAktzo1NL8DgAA".to_string(),
                             tweet: TweetReference {
                                 id: "524573263163572224".to_string(),
                                 sort_index: "371578380871797248".to_string(),
+                                #[cfg(feature = "extra-fields")]
+                                extra: serde_json::Map::new(),
                             },
+                            #[cfg(feature = "extra-fields")]
+                            extra: serde_json::Map::new(),
                         },
                     ],
                     timeline_id: "custom-539487832448843776".to_string(),
+                    #[cfg(feature = "extra-fields")]
+                    extra: serde_json::Map::new(),
                 },
+                #[cfg(feature = "extra-fields")]
+                extra: serde_json::Map::new(),
             },
+            #[cfg(feature = "extra-fields")]
+            extra: serde_json::Map::new(),
         };
 
         assert_eq!(expected, response);
@@ -0,0 +1,52 @@
+//! Reports drift between captured JSON responses and the models in `twitter::api`.
+//!
+//! ```text
+//! schema-drift <model> <dir>
+//! ```
+//!
+//! Every `.json` file in `dir` is checked against `model`. Exits with status 1
+//! if any drift was found.
+
+use std::env;
+use std::io;
+use std::path::Path;
+use std::process;
+use twitter::api::{EntriesResponse, Tweet, User};
+use twitter::drift::{self, Report};
+
+const MODELS: &str = "tweet, tweets, user, entries";
+
+fn check(model: &str, dir: &Path) -> Option<io::Result<Report>> {
+    Some(match model {
+        "tweet" => drift::check_dir::<Tweet>(dir),
+        "tweets" => drift::check_dir::<Vec<Tweet>>(dir),
+        "user" => drift::check_dir::<User>(dir),
+        "entries" => drift::check_dir::<EntriesResponse>(dir),
+        _ => return None,
+    })
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() != 2 {
+        eprintln!("usage: schema-drift <model> <dir>\nmodels: {}", MODELS);
+        process::exit(2);
+    }
+
+    match check(&args[0], Path::new(&args[1])) {
+        Some(Ok(report)) => {
+            print!("{}", report);
+            if !report.is_clean() {
+                process::exit(1);
+            }
+        }
+        Some(Err(e)) => {
+            eprintln!("{}: {}", args[1], e);
+            process::exit(2);
+        }
+        None => {
+            eprintln!("unknown model `{}`, expected one of: {}", args[0], MODELS);
+            process::exit(2);
+        }
+    }
+}
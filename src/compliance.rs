@@ -0,0 +1,308 @@
+//! Keeping stored tweets in line with what their authors and Twitter ask.
+//!
+//! Streams send compliance messages alongside tweets: deletions, requests to
+//! remove location data, and tweets or users withheld in some countries.
+//! The Developer Policy requires stored tweets to honor them. A
+//! [`Compliance`] handler receives them with [`StreamMessage::comply`], and
+//! [`TweetStore`] is a handler that keeps tweets in memory.
+
+use crate::api::Tweet;
+use crate::stream::{ScrubGeo, StatusDeletion, StatusWithheld, StreamMessage, UserWithheld};
+use std::collections::BTreeMap;
+
+/// Applies compliance messages to stored tweets.
+pub trait Compliance {
+    /// Removes a deleted tweet.
+    fn delete(&mut self, deletion: &StatusDeletion);
+
+    /// Removes location data from a user's tweets up to a tweet.
+    fn scrub_geo(&mut self, scrub: &ScrubGeo);
+
+    fn status_withheld(&mut self, withheld: &StatusWithheld);
+
+    fn user_withheld(&mut self, withheld: &UserWithheld);
+}
+
+impl StreamMessage {
+    /// Passes a compliance message to `handler`, returning whether this was
+    /// one.
+    pub fn comply<C: Compliance + ?Sized>(&self, handler: &mut C) -> bool {
+        match self {
+            StreamMessage::Delete(deletion) => handler.delete(deletion),
+            StreamMessage::ScrubGeo(scrub) => handler.scrub_geo(scrub),
+            StreamMessage::StatusWithheld(withheld) => handler.status_withheld(withheld),
+            StreamMessage::UserWithheld(withheld) => handler.user_withheld(withheld),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Tweets kept in memory, by ID.
+///
+/// Deleted and withheld tweets are removed along with retweets of them, and
+/// so are all tweets of withheld users. Quotes of them stay, without the
+/// quoted copy. Scrubbing clears `coordinates`, `geo` and `place`, also of
+/// retweeted and quoted copies.
+#[derive(Clone, Debug, Default)]
+pub struct TweetStore {
+    tweets: BTreeMap<u64, Tweet>,
+}
+
+impl TweetStore {
+    /// Stores the tweets of a stream and applies its compliance messages.
+    pub fn handle(&mut self, message: &StreamMessage) {
+        if let StreamMessage::Tweet(tweet) = message {
+            self.insert((**tweet).clone());
+        } else {
+            message.comply(self);
+        }
+    }
+
+    pub fn insert(&mut self, tweet: Tweet) {
+        self.tweets.insert(tweet.id, tweet);
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Tweet> {
+        self.tweets.get(&id)
+    }
+
+    /// Oldest first.
+    pub fn tweets(&self) -> impl Iterator<Item = &Tweet> {
+        self.tweets.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tweets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tweets.is_empty()
+    }
+
+    /// Removes the tweets `matches` accepts and retweets of them, and drops
+    /// quoted copies it accepts.
+    fn remove<F: Fn(&Tweet) -> bool>(&mut self, matches: F) {
+        self.tweets.retain(|_, tweet| keep(tweet, &matches));
+    }
+}
+
+/// Whether `tweet` stays, after dropping the quoted copies `matches` accepts.
+fn keep<F: Fn(&Tweet) -> bool>(tweet: &mut Tweet, matches: &F) -> bool {
+    if matches(tweet) {
+        return false;
+    }
+    if let Some(retweeted) = &mut tweet.retweeted_status {
+        if !keep(retweeted, matches) {
+            return false;
+        }
+    }
+    if let Some(quoted) = &mut tweet.quoted_status {
+        if !keep(quoted, matches) {
+            tweet.quoted_status = None;
+        }
+    }
+    true
+}
+
+fn scrub(tweet: &mut Tweet, scrub_geo: &ScrubGeo) {
+    if tweet.user.id == scrub_geo.user_id && tweet.id <= scrub_geo.up_to_status_id {
+        tweet.coordinates = None;
+        tweet.geo = None;
+        tweet.place = None;
+    }
+    let retweeted = tweet.retweeted_status.iter_mut();
+    for embedded in retweeted.chain(tweet.quoted_status.iter_mut()) {
+        scrub(embedded, scrub_geo);
+    }
+}
+
+impl Compliance for TweetStore {
+    fn delete(&mut self, deletion: &StatusDeletion) {
+        self.remove(|tweet| tweet.id == deletion.id);
+    }
+
+    fn scrub_geo(&mut self, scrub_geo: &ScrubGeo) {
+        // Newer retweets and quotes can embed scrubbed tweets, so every tweet
+        // is checked.
+        for tweet in self.tweets.values_mut() {
+            scrub(tweet, scrub_geo);
+        }
+    }
+
+    fn status_withheld(&mut self, withheld: &StatusWithheld) {
+        self.remove(|tweet| tweet.id == withheld.id);
+    }
+
+    fn user_withheld(&mut self, withheld: &UserWithheld) {
+        self.remove(|tweet| tweet.user.id == withheld.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn tweet(id: u64, user_id: u64) -> Tweet {
+        let mut tweet: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/tweets/plain_text.json")).unwrap();
+        tweet["id"] = json!(id);
+        tweet["id_str"] = json!(id.to_string());
+        tweet["user"]["id"] = json!(user_id);
+        tweet["coordinates"] = json!({"type": "Point", "coordinates": [-122.4, 37.8]});
+        serde_json::from_value(tweet).unwrap()
+    }
+
+    fn retweet(id: u64, user_id: u64, of: Tweet) -> Tweet {
+        Tweet {
+            retweeted_status: Some(Box::new(of)),
+            ..tweet(id, user_id)
+        }
+    }
+
+    fn quote(id: u64, user_id: u64, of: Tweet) -> Tweet {
+        Tweet {
+            quoted_status: Some(Box::new(of)),
+            ..tweet(id, user_id)
+        }
+    }
+
+    fn ids(store: &TweetStore) -> Vec<u64> {
+        store.tweets().map(|tweet| tweet.id).collect()
+    }
+
+    #[test]
+    fn stores_tweets_and_honors_compliance() {
+        let mut store = TweetStore::default();
+        let messages = vec![
+            StreamMessage::Tweet(Box::new(tweet(1, 100))),
+            StreamMessage::Tweet(Box::new(tweet(2, 100))),
+            StreamMessage::Tweet(Box::new(tweet(3, 200))),
+            StreamMessage::Tweet(Box::new(retweet(4, 300, tweet(3, 200)))),
+            StreamMessage::Tweet(Box::new(tweet(5, 100))),
+            StreamMessage::Tweet(Box::new(tweet(6, 400))),
+            StreamMessage::Tweet(Box::new(retweet(7, 300, tweet(6, 400)))),
+            StreamMessage::Tweet(Box::new(tweet(8, 500))),
+        ];
+        for message in &messages {
+            store.handle(message);
+        }
+        assert_eq!(store.len(), 8);
+
+        store.handle(&StreamMessage::Delete(StatusDeletion {
+            id: 3,
+            user_id: 200,
+        }));
+        assert_eq!(ids(&store), vec![1, 2, 5, 6, 7, 8]);
+
+        store.handle(&StreamMessage::ScrubGeo(ScrubGeo {
+            user_id: 100,
+            up_to_status_id: 2,
+        }));
+        assert!(store.get(1).unwrap().coordinates.is_none());
+        assert!(store.get(2).unwrap().coordinates.is_none());
+        assert!(store.get(5).unwrap().coordinates.is_some());
+        assert!(store.get(6).unwrap().coordinates.is_some());
+
+        store.handle(&StreamMessage::UserWithheld(UserWithheld {
+            id: 400,
+            withheld_in_countries: vec!["DE".to_string()],
+        }));
+        assert_eq!(ids(&store), vec![1, 2, 5, 8]);
+
+        store.handle(&StreamMessage::StatusWithheld(StatusWithheld {
+            id: 8,
+            user_id: 500,
+            withheld_in_countries: vec!["XY".to_string()],
+        }));
+        assert_eq!(ids(&store), vec![1, 2, 5]);
+    }
+
+    #[test]
+    fn honors_compliance_in_embedded_tweets() {
+        let mut store = TweetStore::default();
+        store.insert(quote(10, 300, tweet(3, 200)));
+        store.insert(retweet(11, 300, quote(9, 400, tweet(3, 200))));
+        store.insert(retweet(12, 300, tweet(2, 100)));
+        store.insert(quote(13, 100, tweet(4, 100)));
+
+        store.delete(&StatusDeletion {
+            id: 3,
+            user_id: 200,
+        });
+        assert_eq!(ids(&store), vec![10, 11, 12, 13]);
+        assert!(store.get(10).unwrap().quoted_status.is_none());
+        let retweeted = store.get(11).unwrap().retweeted_status.as_ref().unwrap();
+        assert!(retweeted.quoted_status.is_none());
+
+        store.scrub_geo(&ScrubGeo {
+            user_id: 100,
+            up_to_status_id: 3,
+        });
+        let retweet = store.get(12).unwrap();
+        assert!(retweet.coordinates.is_some());
+        assert!(retweet
+            .retweeted_status
+            .as_ref()
+            .unwrap()
+            .coordinates
+            .is_none());
+        let quote = store.get(13).unwrap();
+        assert!(quote.coordinates.is_some());
+        assert!(quote.quoted_status.as_ref().unwrap().coordinates.is_some());
+
+        store.user_withheld(&UserWithheld {
+            id: 100,
+            withheld_in_countries: vec!["DE".to_string()],
+        });
+        assert_eq!(ids(&store), vec![10, 11]);
+    }
+
+    #[test]
+    fn only_compliance_messages_are_passed_on() {
+        #[derive(Default)]
+        struct Log(Vec<&'static str>);
+
+        impl Compliance for Log {
+            fn delete(&mut self, _: &StatusDeletion) {
+                self.0.push("delete");
+            }
+
+            fn scrub_geo(&mut self, _: &ScrubGeo) {
+                self.0.push("scrub_geo");
+            }
+
+            fn status_withheld(&mut self, _: &StatusWithheld) {
+                self.0.push("status_withheld");
+            }
+
+            fn user_withheld(&mut self, _: &UserWithheld) {
+                self.0.push("user_withheld");
+            }
+        }
+
+        let messages = [
+            r#"{"delete":{"status":{"id":1234,"user_id":3}}}"#,
+            r#"{"limit":{"track":1234}}"#,
+            r#"{"scrub_geo":{"user_id":14090548,"up_to_status_id":23260136625}}"#,
+            r#"{"status_withheld":{"id":1234567890,"user_id":123456,"withheld_in_countries":["DE"]}}"#,
+            r#"{"user_withheld":{"id":123456,"withheld_in_countries":["DE"]}}"#,
+            r#"{"friends":[1,2]}"#,
+        ];
+        let mut log = Log::default();
+        let handled: Vec<_> = messages
+            .iter()
+            .map(|json| {
+                StreamMessage::parse(json.as_bytes())
+                    .unwrap()
+                    .comply(&mut log)
+            })
+            .collect();
+        assert_eq!(handled, [true, false, true, true, true, false]);
+        assert_eq!(
+            log.0,
+            ["delete", "scrub_geo", "status_withheld", "user_withheld"]
+        );
+    }
+}
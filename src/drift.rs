@@ -0,0 +1,1075 @@
+//! Schema drift detection
+//!
+//! Compares captured JSON responses against the models in [`api`](../api/index.html)
+//! and reports, per JSON path, every field our structs do not know about,
+//! every required field that was missing and every value of the wrong type.
+//!
+//! Paths are aggregated: array indices are written as `[]` and the keys of
+//! maps such as `EntryObjects::tweets` as `*`, so the same problem in many
+//! tweets is reported once with a count.
+//!
+//! Models that flatten unknown fields into a map, as all of them do with the
+//! `extra-fields` feature, capture those fields, so they are not reported as
+//! unknown.
+
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess,
+    SeqAccess, VariantAccess, Visitor,
+};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A single difference between a JSON document and our models.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Drift {
+    /// Present in the JSON, absent from our structs.
+    Unknown,
+    /// Required by our structs, absent from the JSON.
+    Missing,
+    /// Present in both, but the JSON value has a different type.
+    Mismatch {
+        expected: String,
+        found: &'static str,
+    },
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Drift::Unknown => write!(f, "unknown field"),
+            Drift::Missing => write!(f, "missing field"),
+            Drift::Mismatch { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+        }
+    }
+}
+
+/// A [`Drift`] at an aggregated JSON path such as `entities.urls[].indices`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Issue {
+    pub path: String,
+    pub drift: Drift,
+}
+
+/// Checks a single JSON document against the model `T`.
+///
+/// Unlike deserializing, this does not stop at the first problem: values of
+/// the wrong type are replaced with defaults and missing fields with
+/// placeholders so the rest of the document is still checked.
+///
+/// Fails when `T` rejects the document for a reason other than its shape,
+/// for example a custom validation in a `Deserialize` impl.
+pub fn check<T: DeserializeOwned>(value: &Value) -> Result<Vec<Issue>, String> {
+    let mut inject = HashMap::new();
+    loop {
+        let context = Context {
+            issues: RefCell::new(Vec::new()),
+            inject,
+        };
+        let root = Lenient {
+            value,
+            path: String::new(),
+            aggregated: String::new(),
+            context: &context,
+        };
+        match T::deserialize(root) {
+            Ok(_) => return Ok(context.issues.into_inner()),
+            // Retry with a placeholder for the field; `Deserialize` impls
+            // only report one missing field at a time.
+            Err(Error::Missing(field, Some(path))) => {
+                inject = context.inject;
+                if inject.insert(path.clone(), field).is_some() {
+                    return Err(format!("missing field `{}` could not be injected", path));
+                }
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+/// Aggregated drift over many documents.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    /// Number of documents checked.
+    pub documents: usize,
+    /// Files that could not be read or parsed as JSON at all.
+    pub errors: Vec<(PathBuf, String)>,
+    /// Number of documents with each drift, keyed by aggregated JSON path.
+    pub paths: BTreeMap<String, BTreeMap<Drift, usize>>,
+}
+
+impl Report {
+    /// Checks `value` against `T` and adds the result to the report.
+    pub fn add<T: DeserializeOwned>(&mut self, value: &Value) -> Result<(), String> {
+        self.documents += 1;
+        for issue in check::<T>(value)? {
+            *self
+                .paths
+                .entry(issue.path)
+                .or_default()
+                .entry(issue.drift)
+                .or_insert(0) += 1;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if no document drifted from the models.
+    pub fn is_clean(&self) -> bool {
+        self.errors.is_empty() && self.paths.is_empty()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "checked {} documents", self.documents)?;
+        for (path, error) in &self.errors {
+            writeln!(f, "error    {}: {}", path.display(), error)?;
+        }
+        for (path, drifts) in &self.paths {
+            for (drift, count) in drifts {
+                let path = if path.is_empty() { "<root>" } else { path };
+                writeln!(f, "{:<8} {}: {} ({})", kind(drift), path, drift, count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn kind(drift: &Drift) -> &'static str {
+    match drift {
+        Drift::Unknown => "unknown",
+        Drift::Missing => "missing",
+        Drift::Mismatch { .. } => "mismatch",
+    }
+}
+
+/// Checks every `.json` file in `dir` against the model `T`.
+pub fn check_dir<T: DeserializeOwned>(dir: &Path) -> io::Result<Report> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut report = Report::default();
+    for path in paths {
+        let result = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str::<Value>(&json).map_err(|e| e.to_string()))
+            .and_then(|value| report.add::<T>(&value));
+        if let Err(error) = result {
+            report.errors.push((path, error));
+        }
+    }
+    Ok(report)
+}
+
+struct Context {
+    issues: RefCell<Vec<Issue>>,
+    /// Concrete paths (with real indices and keys) of missing fields to
+    /// fill with placeholders, and the fields' names.
+    inject: HashMap<String, &'static str>,
+}
+
+impl Context {
+    fn record(&self, path: &str, drift: Drift) {
+        let issue = Issue {
+            path: path.to_string(),
+            drift,
+        };
+        let mut issues = self.issues.borrow_mut();
+        if !issues.contains(&issue) {
+            issues.push(issue);
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Error {
+    /// A required field was missing, with the concrete path once known.
+    Missing(&'static str, Option<String>),
+    Other(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Missing(field, _) => write!(f, "missing field `{}`", field),
+            Error::Other(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Other(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::Missing(field, None)
+    }
+}
+
+fn join(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Deserializer over a JSON value that records drift instead of failing.
+struct Lenient<'a, 'de> {
+    value: &'de Value,
+    /// Concrete path, used to inject missing fields.
+    path: String,
+    /// Aggregated path, used for reporting.
+    aggregated: String,
+    context: &'a Context,
+}
+
+impl<'a, 'de> Lenient<'a, 'de> {
+    fn mismatch(&self, expected: &str) {
+        self.context.record(
+            &self.aggregated,
+            Drift::Mismatch {
+                expected: expected.to_string(),
+                found: type_name(self.value),
+            },
+        );
+    }
+
+    fn unsigned(&self, max: u64) -> u64 {
+        match self.value.as_u64() {
+            Some(n) if n <= max => n,
+            _ => {
+                self.mismatch("unsigned integer");
+                0
+            }
+        }
+    }
+
+    fn signed(&self, min: i64, max: i64) -> i64 {
+        match self.value.as_i64() {
+            Some(n) if n >= min && n <= max => n,
+            _ => {
+                self.mismatch("integer");
+                0
+            }
+        }
+    }
+}
+
+macro_rules! lenient_unsigned {
+    ($($method:ident => $ty:ty,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                visitor.visit_u64(self.unsigned(<$ty>::MAX as u64))
+            }
+        )*
+    };
+}
+
+macro_rules! lenient_signed {
+    ($($method:ident => $ty:ty,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                visitor.visit_i64(self.signed(<$ty>::MIN as i64, <$ty>::MAX as i64))
+            }
+        )*
+    };
+}
+
+impl<'a, 'de> Deserializer<'de> for Lenient<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(*b),
+            Value::Number(n) => {
+                if let Some(n) = n.as_u64() {
+                    visitor.visit_u64(n)
+                } else if let Some(n) = n.as_i64() {
+                    visitor.visit_i64(n)
+                } else {
+                    visitor.visit_f64(n.as_f64().unwrap_or_default())
+                }
+            }
+            Value::String(s) => visitor.visit_borrowed_str(s),
+            Value::Array(_) => self.deserialize_seq(visitor),
+            Value::Object(_) => self.deserialize_map(visitor),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Bool(b) => visitor.visit_bool(*b),
+            _ => {
+                self.mismatch("bool");
+                visitor.visit_bool(false)
+            }
+        }
+    }
+
+    lenient_unsigned! {
+        deserialize_u8 => u8,
+        deserialize_u16 => u16,
+        deserialize_u32 => u32,
+        deserialize_u64 => u64,
+    }
+
+    lenient_signed! {
+        deserialize_i8 => i8,
+        deserialize_i16 => i16,
+        deserialize_i32 => i32,
+        deserialize_i64 => i64,
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value.as_f64() {
+            Some(n) => visitor.visit_f64(n),
+            None => {
+                self.mismatch("number");
+                visitor.visit_f64(0.0)
+            }
+        }
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let mut chars = self.value.as_str().unwrap_or_default().chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => {
+                self.mismatch("character");
+                visitor.visit_char('\0')
+            }
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::String(s) => visitor.visit_borrowed_str(s),
+            _ => {
+                self.mismatch("string");
+                visitor.visit_borrowed_str("")
+            }
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if !self.value.is_null() {
+            self.mismatch("null");
+        }
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        static EMPTY: Vec<Value> = Vec::new();
+        let items = match self.value {
+            Value::Array(items) => items,
+            _ => {
+                self.mismatch("array");
+                &EMPTY
+            }
+        };
+        visitor.visit_seq(LenientSeq {
+            items: items.iter().enumerate(),
+            path: &self.path,
+            aggregated: format!("{}[]", self.aggregated),
+            context: self.context,
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        // Structs with flattened fields are deserialized as maps. Their
+        // unknown fields go to the flattened map rather than being reported,
+        // and their keys are field names.
+        let is_struct = Expecting(&visitor).to_string().starts_with("struct ");
+        let mut entries: Vec<_> = match self.value {
+            Value::Object(map) => map.iter().map(|(k, v)| (k.as_str(), Some(v))).collect(),
+            _ => {
+                self.mismatch("object");
+                Vec::new()
+            }
+        };
+        if is_struct {
+            for (path, field) in &self.context.inject {
+                if *path == join(&self.path, field) && !entries.iter().any(|(k, _)| k == field) {
+                    entries.push((field, None));
+                }
+            }
+        }
+
+        let result = visitor.visit_map(LenientMap {
+            entries: entries.into_iter(),
+            value: None,
+            path: &self.path,
+            aggregated: &self.aggregated,
+            dynamic: !is_struct,
+            context: self.context,
+        });
+        match result {
+            Err(Error::Missing(field, None)) if is_struct => {
+                Err(Error::Missing(field, Some(join(&self.path, field))))
+            }
+            result => result,
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let map = match self.value {
+            Value::Object(map) => map,
+            _ => {
+                self.mismatch("object");
+                return Placeholder.deserialize_struct(_name, fields, visitor);
+            }
+        };
+
+        let mut entries = Vec::new();
+        for (key, value) in map {
+            if fields.contains(&key.as_str()) {
+                entries.push((key.as_str(), Some(value)));
+            } else {
+                self.context
+                    .record(&join(&self.aggregated, key), Drift::Unknown);
+            }
+        }
+        for field in fields {
+            if !map.contains_key(*field)
+                && self.context.inject.contains_key(&join(&self.path, field))
+            {
+                entries.push((field, None));
+            }
+        }
+
+        let result = visitor.visit_map(LenientMap {
+            entries: entries.into_iter(),
+            value: None,
+            path: &self.path,
+            aggregated: &self.aggregated,
+            dynamic: false,
+            context: self.context,
+        });
+        match result {
+            Err(Error::Missing(field, None)) => {
+                Err(Error::Missing(field, Some(join(&self.path, field))))
+            }
+            result => result,
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.value {
+            Value::String(s) if variants.contains(&s.as_str()) => {
+                visitor.visit_enum(s.as_str().into_deserializer())
+            }
+            Value::Object(map) if map.len() == 1 => {
+                let (variant, value) = map.iter().next().unwrap();
+                visitor.visit_enum(LenientEnum {
+                    variant,
+                    value: Lenient {
+                        value,
+                        path: join(&self.path, variant),
+                        aggregated: join(&self.aggregated, variant),
+                        context: self.context,
+                    },
+                })
+            }
+            _ => {
+                self.mismatch(&format!("one of {:?}", variants));
+                Placeholder.deserialize_enum(_name, variants, visitor)
+            }
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+}
+
+struct LenientSeq<'a, I> {
+    items: I,
+    path: &'a str,
+    aggregated: String,
+    context: &'a Context,
+}
+
+impl<'a, 'de, I> SeqAccess<'de> for LenientSeq<'a, I>
+where
+    I: Iterator<Item = (usize, &'de Value)>,
+{
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        match self.items.next() {
+            Some((index, value)) => seed
+                .deserialize(Lenient {
+                    value,
+                    path: format!("{}[{}]", self.path, index),
+                    aggregated: self.aggregated.clone(),
+                    context: self.context,
+                })
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Entries with a `None` value are missing fields filled with placeholders.
+struct LenientMap<'a, 'de, I> {
+    entries: I,
+    value: Option<(&'de str, Option<&'de Value>)>,
+    path: &'a str,
+    aggregated: &'a str,
+    /// Keys are data (such as tweet IDs) rather than field names.
+    dynamic: bool,
+    context: &'a Context,
+}
+
+impl<'a, 'de, I> MapAccess<'de> for LenientMap<'a, 'de, I>
+where
+    I: Iterator<Item = (&'de str, Option<&'de Value>)>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some((key, value));
+                seed.deserialize(BorrowedStrDeserializer::new(key))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| Error::Other("value requested before key".to_string()))?;
+        let aggregated = join(self.aggregated, if self.dynamic { "*" } else { key });
+        match value {
+            Some(value) => seed.deserialize(Lenient {
+                value,
+                path: join(self.path, key),
+                aggregated,
+                context: self.context,
+            }),
+            None => {
+                self.context.record(&aggregated, Drift::Missing);
+                seed.deserialize(Placeholder)
+            }
+        }
+    }
+}
+
+struct LenientEnum<'a, 'de> {
+    variant: &'de str,
+    value: Lenient<'a, 'de>,
+}
+
+impl<'a, 'de> EnumAccess<'de> for LenientEnum<'a, 'de> {
+    type Error = Error;
+    type Variant = Lenient<'a, 'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Error> {
+        let variant = seed.deserialize(BorrowedStrDeserializer::<Error>::new(self.variant))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'a, 'de> VariantAccess<'de> for Lenient<'a, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_struct("", fields, visitor)
+    }
+}
+
+/// What a visitor expects, as in its error messages.
+struct Expecting<'a, V>(&'a V);
+
+impl<'a, 'de, V: Visitor<'de>> fmt::Display for Expecting<'a, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.expecting(f)
+    }
+}
+
+/// Produces the simplest value of whatever type is requested, so a missing
+/// or mistyped value does not stop the rest of the document being checked.
+struct Placeholder;
+
+macro_rules! placeholder {
+    ($($method:ident => $visit:ident($value:expr),)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                visitor.$visit($value)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Placeholder {
+    type Error = Error;
+
+    placeholder! {
+        deserialize_bool => visit_bool(false),
+        deserialize_u8 => visit_u64(0),
+        deserialize_u16 => visit_u64(0),
+        deserialize_u32 => visit_u64(0),
+        deserialize_u64 => visit_u64(0),
+        deserialize_i8 => visit_i64(0),
+        deserialize_i16 => visit_i64(0),
+        deserialize_i32 => visit_i64(0),
+        deserialize_i64 => visit_i64(0),
+        deserialize_f32 => visit_f64(0.0),
+        deserialize_f64 => visit_f64(0.0),
+        deserialize_char => visit_char('\0'),
+        deserialize_str => visit_borrowed_str(""),
+        deserialize_string => visit_borrowed_str(""),
+        deserialize_identifier => visit_borrowed_str(""),
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_bytes(&[])
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_bytes(&[])
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_none()
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(de::value::SeqDeserializer::<_, Error>::new(
+            std::iter::empty::<()>(),
+        ))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(PlaceholderMap(std::iter::empty()))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_map(PlaceholderMap(fields.iter()))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let variant = variants.first().copied().unwrap_or_default();
+        visitor.visit_enum(BorrowedStrDeserializer::new(variant))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+}
+
+/// Every field of a struct, each with a placeholder value.
+struct PlaceholderMap<I>(I);
+
+impl<'de, I> MapAccess<'de> for PlaceholderMap<I>
+where
+    I: Iterator<Item = &'static &'static str>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.0.next() {
+            Some(field) => seed
+                .deserialize(BorrowedStrDeserializer::new(field))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        seed.deserialize(Placeholder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Tweet;
+    use serde_json::{json, Map};
+    use std::collections::HashMap;
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    struct Status {
+        id: u64,
+        text: String,
+        truncated: bool,
+        retweet_count: u64,
+        lang: Option<String>,
+        entities: Entities,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    struct Entities {
+        hashtags: Vec<Hashtag>,
+        user_mentions: Vec<Mention>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    struct Hashtag {
+        text: String,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    struct Mention {
+        id: u64,
+        screen_name: String,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    struct Timeline {
+        statuses: HashMap<String, Status>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    struct Flattened {
+        id: u64,
+        retweet_count: u64,
+        entities: Entities,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    }
+
+    fn status() -> Value {
+        json!({
+            "id": 1,
+            "text": "@TwitterDev @TwitterAPI #rust",
+            "truncated": false,
+            "retweet_count": 0,
+            "lang": null,
+            "entities": {
+                "hashtags": [{"text": "rust"}],
+                "user_mentions": [
+                    {"id": 2244994945u64, "screen_name": "TwitterDev"},
+                    {"id": 6253282, "screen_name": "TwitterAPI"},
+                ],
+            },
+        })
+    }
+
+    fn issue(path: &str, drift: Drift) -> Issue {
+        Issue {
+            path: path.to_string(),
+            drift,
+        }
+    }
+
+    #[test]
+    fn matching_document_has_no_issues() {
+        assert_eq!(check::<Status>(&status()).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn reports_unknown_fields() {
+        let mut status = status();
+        status["edit_history"] = json!([]);
+        status["entities"]["user_mentions"][1]["verified"] = json!(true);
+
+        assert_eq!(
+            check::<Status>(&status).unwrap(),
+            vec![
+                issue("edit_history", Drift::Unknown),
+                issue("entities.user_mentions[].verified", Drift::Unknown),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_every_missing_field() {
+        let mut status = status();
+        let object = status.as_object_mut().unwrap();
+        object.remove("text");
+        object.remove("retweet_count");
+        object.remove("lang");
+        status["entities"]["user_mentions"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("screen_name");
+
+        let issues = check::<Status>(&status).unwrap();
+        assert_eq!(issues.len(), 3);
+        assert!(issues.contains(&issue("text", Drift::Missing)));
+        assert!(issues.contains(&issue("retweet_count", Drift::Missing)));
+        assert!(issues.contains(&issue(
+            "entities.user_mentions[].screen_name",
+            Drift::Missing
+        )));
+    }
+
+    #[test]
+    fn reports_type_mismatches() {
+        let mut status = status();
+        status["entities"]["hashtags"] = json!(["rust"]);
+        status["retweet_count"] = json!(-1);
+        status["truncated"] = json!("false");
+
+        assert_eq!(
+            check::<Status>(&status).unwrap(),
+            vec![
+                issue(
+                    "entities.hashtags[]",
+                    Drift::Mismatch {
+                        expected: "object".to_string(),
+                        found: "string",
+                    },
+                ),
+                issue(
+                    "retweet_count",
+                    Drift::Mismatch {
+                        expected: "unsigned integer".to_string(),
+                        found: "number",
+                    },
+                ),
+                issue(
+                    "truncated",
+                    Drift::Mismatch {
+                        expected: "bool".to_string(),
+                        found: "string",
+                    },
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn aggregates_map_keys_and_indices() {
+        let timeline = json!({
+            "statuses": {
+                "1": {"id": 1},
+                "2": {"id": 2},
+            },
+        });
+
+        let mut report = Report::default();
+        report.add::<Timeline>(&timeline).unwrap();
+        report.add::<Timeline>(&timeline).unwrap();
+
+        assert_eq!(report.documents, 2);
+        assert_eq!(report.paths["statuses.*.text"][&Drift::Missing], 2);
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn checks_flattened_fields() {
+        let document = json!([
+            {"id": 1, "text": "kept in extra", "entities": {"hashtags": [], "user_mentions": []}},
+            {"id": "2", "entities": {"hashtags": [{}], "user_mentions": []}},
+        ]);
+        let issues = check::<Vec<Flattened>>(&document).unwrap();
+        assert_eq!(issues.len(), 3);
+        assert!(issues.contains(&issue(
+            "[].id",
+            Drift::Mismatch {
+                expected: "unsigned integer".to_string(),
+                found: "string",
+            },
+        )));
+        assert!(issues.contains(&issue("[].entities.hashtags[].text", Drift::Missing)));
+        assert!(issues.contains(&issue("[].retweet_count", Drift::Missing)));
+    }
+
+    #[test]
+    fn fixtures_match_models() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tweets");
+        let report = check_dir::<Tweet>(&dir).unwrap();
+        assert!(report.errors.is_empty(), "{}", report);
+        if cfg!(feature = "extra-fields") {
+            assert!(report.is_clean(), "{}", report);
+        }
+        for (path, drifts) in &report.paths {
+            assert!(
+                drifts.keys().all(|drift| *drift == Drift::Unknown),
+                "{}: {:?}",
+                path,
+                drifts
+            );
+        }
+    }
+}
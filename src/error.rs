@@ -0,0 +1,142 @@
+use crate::media::{MediaId, ProcessingError};
+use std::error;
+use std::fmt;
+use std::io;
+
+/// Error code Twitter uses for a status identical to a recent one.
+const DUPLICATE_STATUS: u32 = 187;
+
+/// An error reported by Twitter, from the `errors` array of a response.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ApiError {
+    pub code: u32,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The transport failed to send the request or read the response.
+    Io(io::Error),
+    /// The response was not the JSON expected.
+    Json(serde_json::Error),
+    /// Twitter answered with an error status.
+    Api { status: u16, errors: Vec<ApiError> },
+    /// The status is identical to one recently posted from the account
+    /// (error 187), so it was not posted again.
+    DuplicateStatus,
+    /// Twitter could not process uploaded media.
+    MediaProcessing(ProcessingError),
+    /// Twitter was still processing uploaded media when
+    /// [`Twitter::wait_for_upload`](crate::Twitter::wait_for_upload) gave up.
+    MediaProcessingTimedOut(MediaId),
+    /// The request is outside Twitter's limits, so it was not sent.
+    InvalidRequest(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Builds the error for a response with an error `status`.
+    pub(crate) fn from_response(status: u16, body: &[u8]) -> Error {
+        #[derive(Deserialize)]
+        struct Errors {
+            errors: Vec<ApiError>,
+        }
+
+        let errors = serde_json::from_slice::<Errors>(body)
+            .map(|body| body.errors)
+            .unwrap_or_default();
+        if errors.iter().any(|error| error.code == DUPLICATE_STATUS) {
+            Error::DuplicateStatus
+        } else {
+            Error::Api { status, errors }
+        }
+    }
+
+    /// Twitter's error codes, empty for errors that did not come from it.
+    pub fn codes(&self) -> Vec<u32> {
+        match self {
+            Error::Api { errors, .. } => errors.iter().map(|error| error.code).collect(),
+            Error::DuplicateStatus => vec![DUPLICATE_STATUS],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "transport error: {}", error),
+            Error::Json(error) => write!(f, "unexpected response: {}", error),
+            Error::Api { status, errors } => {
+                write!(f, "Twitter returned {}", status)?;
+                for (i, error) in errors.iter().enumerate() {
+                    let separator = if i == 0 { ": " } else { "; " };
+                    write!(f, "{}{} (code {})", separator, error.message, error.code)?;
+                }
+                Ok(())
+            }
+            Error::DuplicateStatus => f.write_str("status is a duplicate"),
+            Error::MediaProcessing(error) => {
+                write!(
+                    f,
+                    "media processing failed: {} ({})",
+                    error.message, error.name
+                )
+            }
+            Error::MediaProcessingTimedOut(id) => {
+                write!(f, "media {} is still processing", id)
+            }
+            Error::InvalidRequest(reason) => write!(f, "invalid request: {}", reason),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::Json(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Error {
+        Error::Json(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_from_responses() {
+        let error = Error::from_response(
+            403,
+            br#"{"errors":[{"code":187,"message":"Status is a duplicate."}]}"#,
+        );
+        assert!(matches!(error, Error::DuplicateStatus));
+
+        let error = Error::from_response(
+            404,
+            br#"{"errors":[{"code":144,"message":"No status found with that ID."}]}"#,
+        );
+        assert_eq!(error.codes(), vec![144]);
+        assert_eq!(
+            error.to_string(),
+            "Twitter returned 404: No status found with that ID. (code 144)"
+        );
+
+        let error = Error::from_response(502, b"<html>Bad Gateway</html>");
+        assert!(matches!(error, Error::Api { status: 502, ref errors } if errors.is_empty()));
+    }
+}
@@ -0,0 +1,371 @@
+//! The HTTP layer under [`Twitter`](crate::Twitter).
+//!
+//! The client builds [`Request`]s and hands them to a [`Transport`], which
+//! signs and sends them with whatever HTTP stack the application uses.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::io::{self, Read};
+use std::thread;
+use std::time::Duration;
+
+/// Base URL of the v1.1 REST API.
+pub const API_URL: &str = "https://api.twitter.com/1.1";
+
+/// Base URL of the v2 API.
+pub const API_V2_URL: &str = "https://api.twitter.com/2";
+
+/// Base URL of the v1.1 media endpoints.
+pub const UPLOAD_URL: &str = "https://upload.twitter.com/1.1";
+
+/// Base URL of the v1.1 streaming endpoints.
+pub const STREAM_URL: &str = "https://stream.twitter.com/1.1";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Delete,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Body {
+    Empty,
+    /// Parameters to send `application/x-www-form-urlencoded`; OAuth 1.0a
+    /// signatures cover them.
+    Form(Vec<(String, String)>),
+    Json(Value),
+    /// Named binary parts to send `multipart/form-data`. OAuth 1.0a
+    /// signatures leave them out, so other parameters go in the query.
+    Multipart(Vec<(String, Vec<u8>)>),
+}
+
+/// A request for a [`Transport`] to send.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Request {
+    pub method: Method,
+    /// URL without the query string.
+    pub url: String,
+    /// Query string parameters, not yet percent-encoded.
+    pub query: Vec<(String, String)>,
+    pub body: Body,
+}
+
+impl Request {
+    /// A GET request for a v1.1 `path`, like `statuses/show`.
+    pub fn get(path: &str, query: Vec<(String, String)>) -> Request {
+        Request {
+            method: Method::Get,
+            url: format!("{}/{}.json", API_URL, path),
+            query,
+            body: Body::Empty,
+        }
+    }
+
+    /// A POST request for a v1.1 `path` with form parameters.
+    pub fn post(path: &str, form: Vec<(String, String)>) -> Request {
+        Request {
+            method: Method::Post,
+            url: format!("{}/{}.json", API_URL, path),
+            query: Vec::new(),
+            body: Body::Form(form),
+        }
+    }
+
+    /// A request for a v2 `path`, like `tweets/search/stream`.
+    pub fn v2(method: Method, path: &str, query: Vec<(String, String)>, body: Body) -> Request {
+        Request {
+            method,
+            url: format!("{}/{}", API_V2_URL, path),
+            query,
+            body,
+        }
+    }
+
+    /// A POST request for a media `path`, like `media/upload`.
+    pub fn upload(path: &str, query: Vec<(String, String)>, body: Body) -> Request {
+        Request {
+            method: Method::Post,
+            url: format!("{}/{}.json", UPLOAD_URL, path),
+            query,
+            body,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    /// Header names are lowercase.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// The first header called `name`, which must be lowercase.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A response whose body is read as it arrives, for streams.
+pub struct Streaming {
+    pub status: u16,
+    /// Header names are lowercase.
+    pub headers: Vec<(String, String)>,
+    pub body: Box<dyn Read + Send>,
+}
+
+impl fmt::Debug for Streaming {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Streaming")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+
+/// Sends requests to Twitter.
+///
+/// Implementations are responsible for authentication, usually by signing
+/// requests with OAuth 1.0a or adding a bearer token, and should return
+/// error statuses as responses rather than errors.
+pub trait Transport: Send + Sync {
+    fn send(&self, request: &Request) -> io::Result<Response>;
+
+    /// Sends `request` without waiting for the whole response, for
+    /// long-lived connections like streams.
+    ///
+    /// Streams notice stalls themselves, after
+    /// [`STALL_TIMEOUT`](crate::stream::STALL_TIMEOUT) without data, so the
+    /// body may block for as long as the connection is open.
+    ///
+    /// Transports that cannot stream keep the default implementation, which
+    /// fails with [`io::ErrorKind::Unsupported`].
+    fn open(&self, request: &Request) -> io::Result<Streaming> {
+        let _ = request;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "this transport cannot stream",
+        ))
+    }
+
+    /// Blocks for `duration` before the client polls again, as when waiting
+    /// for media processing.
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
+/// Flattens a request struct into parameters.
+///
+/// `None` fields are left out and lists are joined with commas, as Twitter
+/// expects for parameters like `media_ids`.
+pub(crate) fn params<T: Serialize>(request: &T) -> Vec<(String, String)> {
+    let object = match serde_json::to_value(request) {
+        Ok(Value::Object(object)) => object,
+        _ => panic!("requests must serialize to objects"),
+    };
+    object
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let value = match value {
+                Value::Null => return None,
+                Value::Array(values) => values.iter().map(param).collect::<Vec<_>>().join(","),
+                value => param(&value),
+            };
+            Some((name, value))
+        })
+        .collect()
+}
+
+/// Splits a query string like `?max_id=1&q=a%20b` into decoded parameters.
+pub(crate) fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_at(pair.find('=').unwrap_or(pair.len()));
+            (decode(name), decode(value.trim_start_matches('=')))
+        })
+        .collect()
+}
+
+/// Reverses percent-encoding, with `+` for spaces.
+fn decode(encoded: &str) -> String {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+            (b'+', _) => decoded.push(b' '),
+            (byte, _) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn param(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod mock {
+    use super::{Request, Response, Streaming, Transport};
+    use std::collections::VecDeque;
+    use std::io::{self, Cursor};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// A transport replaying canned responses and recording requests.
+    ///
+    /// Clones share their state, so a test can keep one to inspect what the
+    /// client it gave the other to has sent.
+    #[derive(Clone, Default)]
+    pub struct Mock {
+        state: Arc<Mutex<State>>,
+    }
+
+    #[derive(Default)]
+    struct State {
+        responses: VecDeque<io::Result<Response>>,
+        streams: VecDeque<io::Result<Streaming>>,
+        requests: Vec<Request>,
+        sleeps: Vec<Duration>,
+    }
+
+    impl Mock {
+        /// Queues a response with a JSON `body`.
+        pub fn respond(&self, status: u16, body: &str) -> &Mock {
+            self.respond_with(Ok(Response {
+                status,
+                headers: vec![("content-type".to_string(), "application/json".to_string())],
+                body: body.as_bytes().to_vec(),
+            }))
+        }
+
+        pub fn respond_with(&self, response: io::Result<Response>) -> &Mock {
+            self.state.lock().unwrap().responses.push_back(response);
+            self
+        }
+
+        /// Queues a streaming response that ends after `body`.
+        pub fn stream(&self, status: u16, body: &str) -> &Mock {
+            self.stream_with(Ok(Streaming {
+                status,
+                headers: Vec::new(),
+                body: Box::new(Cursor::new(body.as_bytes().to_vec())),
+            }))
+        }
+
+        pub fn stream_with(&self, stream: io::Result<Streaming>) -> &Mock {
+            self.state.lock().unwrap().streams.push_back(stream);
+            self
+        }
+
+        pub fn requests(&self) -> Vec<Request> {
+            self.state.lock().unwrap().requests.clone()
+        }
+
+        /// How long the client asked to sleep, without sleeping.
+        pub fn sleeps(&self) -> Vec<Duration> {
+            self.state.lock().unwrap().sleeps.clone()
+        }
+    }
+
+    impl Transport for Mock {
+        fn send(&self, request: &Request) -> io::Result<Response> {
+            let mut state = self.state.lock().unwrap();
+            state.requests.push(request.clone());
+            state
+                .responses
+                .pop_front()
+                .unwrap_or_else(|| panic!("unexpected request {:?}", request))
+        }
+
+        fn open(&self, request: &Request) -> io::Result<Streaming> {
+            let mut state = self.state.lock().unwrap();
+            state.requests.push(request.clone());
+            state
+                .streams
+                .pop_front()
+                .unwrap_or_else(|| panic!("unexpected stream {:?}", request))
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.state.lock().unwrap().sleeps.push(duration);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Example {
+        status: String,
+        in_reply_to_status_id: Option<u64>,
+        media_ids: Option<Vec<u64>>,
+        possibly_sensitive: Option<bool>,
+        lat: Option<f64>,
+    }
+
+    #[test]
+    fn params_skip_none_and_join_lists() {
+        let mut found = params(&Example {
+            status: "hello, world".to_string(),
+            in_reply_to_status_id: None,
+            media_ids: Some(vec![1, 2]),
+            possibly_sensitive: Some(false),
+            lat: Some(37.5),
+        });
+        found.sort();
+        let expected = [
+            ("lat", "37.5"),
+            ("media_ids", "1,2"),
+            ("possibly_sensitive", "false"),
+            ("status", "hello, world"),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn parse_query_strings() {
+        let found = parse_query("?max_id=1124&q=%23rust%20OR+caf%C3%A9&include_entities=1&bad=%zz");
+        let expected = [
+            ("max_id", "1124"),
+            ("q", "#rust OR café"),
+            ("include_entities", "1"),
+            ("bad", "%zz"),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        assert_eq!(found, expected);
+    }
+}
@@ -1,7 +1,51 @@
 #[macro_use] extern crate serde_derive;
 
 pub mod api;
+pub mod compliance;
+pub mod drift;
+pub mod error;
+pub mod http;
+pub mod media;
+pub mod premium;
+pub mod rule;
+pub mod search;
+pub mod statuses;
+pub mod stream;
+pub mod text;
+pub mod timelines;
+pub mod v2;
+
+pub use crate::error::{Error, Result};
+
+use crate::http::{Request, Response, Transport};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
 
 /// Efficient Rust Library for Twitter API
+pub struct Twitter {
+    transport: Arc<dyn Transport>,
+}
+
+impl Twitter {
+    /// A client sending its requests through `transport`.
+    pub fn new<T: Transport + 'static>(transport: T) -> Twitter {
+        Twitter {
+            transport: Arc::new(transport),
+        }
+    }
+
+    /// Sends `request` and parses the JSON response.
+    pub(crate) fn call<T: DeserializeOwned>(&self, request: &Request) -> Result<T> {
+        let response = self.send(request)?;
+        Ok(serde_json::from_slice(&response.body)?)
+    }
 
-pub struct Twitter {}
+    /// Sends `request`, for endpoints answering without a body.
+    pub(crate) fn send(&self, request: &Request) -> Result<Response> {
+        let response = self.transport.send(request)?;
+        if response.status >= 400 {
+            return Err(Error::from_response(response.status, &response.body));
+        }
+        Ok(response)
+    }
+}
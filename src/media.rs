@@ -0,0 +1,711 @@
+//! Uploading photos, GIFs and videos to attach to tweets.
+//!
+//! Small images can go in one request with [`Twitter::upload_media`]. GIFs,
+//! videos and anything larger are sent in chunks with
+//! [`Twitter::upload_media_chunked`], or step by step with
+//! [`Twitter::init_upload`], [`Twitter::append_upload`] and
+//! [`Twitter::finalize_upload`] to resume after a failed chunk.
+//!
+//! Sources are read with [`std::io::Read`]. Like the rest of the client,
+//! uploads have no async variant, so read async sources on a blocking task.
+//!
+//! Once uploaded, photos can be described for screen readers with
+//! [`Twitter::create_media_metadata`] and videos captioned with
+//! [`Twitter::create_subtitles`].
+
+use crate::http::{self, Body, Method, Request, UPLOAD_URL};
+use crate::{Error, Result, Twitter};
+use serde_json::json;
+use std::fmt;
+use std::io::{self, Read};
+use std::time::Duration;
+
+/// Chunk size of [`UploadRequest::new`].
+pub const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// The largest chunk Twitter accepts, 5 MB.
+pub const MAX_CHUNK_SIZE: usize = 5 * 1024 * 1024;
+
+/// How long [`Twitter::wait_for_upload`] waits for processing, in total.
+pub const MAX_PROCESSING_WAIT: Duration = Duration::from_secs(10 * 60);
+
+/// The longest alt text Twitter accepts, in characters.
+pub const MAX_ALT_TEXT_LENGTH: usize = 1000;
+
+/// An uploaded media, to attach with `UpdateRequest::media_ids`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct MediaId(pub u64);
+
+impl fmt::Display for MediaId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// What an upload will be used for, which decides the limits and processing
+/// applied to it.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaCategory {
+    TweetImage,
+    TweetGif,
+    TweetVideo,
+    DmImage,
+    DmGif,
+    DmVideo,
+    /// SRT captions for a video.
+    Subtitles,
+}
+
+/// Media/upload INIT request
+///
+/// # Resource Information
+///
+/// Response formats: **JSON**
+///
+/// Requires authentication?: **Yes (user context only)**
+///
+/// Rate limited?: **Yes**
+///
+/// [Docs on Twitter](https://developer.twitter.com/en/docs/media/upload-media/api-reference/post-media-upload-init)
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct UploadRequest {
+    /// MIME type of the media, like `video/mp4`.
+    pub media_type: String,
+
+    /// Size of the media in bytes.
+    pub total_bytes: u64,
+
+    /// Required for GIFs and videos to be processed for tweets.
+    pub media_category: Option<MediaCategory>,
+
+    /// Other users allowed to tweet the media.
+    pub additional_owners: Option<Vec<u64>>,
+
+    /// Bytes to send with each APPEND, at most [`MAX_CHUNK_SIZE`].
+    #[serde(skip, default = "default_chunk_size")]
+    pub chunk_size: usize,
+}
+
+fn default_chunk_size() -> usize {
+    DEFAULT_CHUNK_SIZE
+}
+
+impl UploadRequest {
+    pub fn new<S: Into<String>>(media_type: S, total_bytes: u64) -> UploadRequest {
+        UploadRequest {
+            media_type: media_type.into(),
+            total_bytes,
+            media_category: None,
+            additional_owners: None,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+}
+
+/// The response to `media/upload`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Upload {
+    pub media_id: MediaId,
+    pub media_id_string: String,
+    pub size: Option<u64>,
+    /// How long the media can be attached for.
+    pub expires_after_secs: Option<u64>,
+    /// Present while GIFs and videos are processed after FINALIZE.
+    pub processing_info: Option<ProcessingInfo>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ProcessingInfo {
+    pub state: ProcessingState,
+    /// When to check on the processing again.
+    pub check_after_secs: Option<u64>,
+    pub progress_percent: Option<u32>,
+    pub error: Option<ProcessingError>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessingState {
+    Pending,
+    InProgress,
+    Failed,
+    Succeeded,
+}
+
+/// Why Twitter could not process a media, like `InvalidMedia`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ProcessingError {
+    pub code: u32,
+    pub name: String,
+    pub message: String,
+}
+
+/// Captions for a video, uploaded as an SRT file with
+/// [`MediaCategory::Subtitles`].
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Subtitle {
+    pub media_id: MediaId,
+    /// BCP 47 code of the caption language, like `EN`.
+    pub language_code: String,
+    /// Shown in the caption picker, like `English`.
+    pub display_name: String,
+}
+
+/// A chunked upload between INIT and FINALIZE.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ChunkedUpload {
+    pub media_id: MediaId,
+    /// Index of the next chunk to APPEND.
+    pub segment: u32,
+}
+
+impl Twitter {
+    /// Uploads a photo in a single request.
+    pub fn upload_media<R: Read>(
+        &self,
+        mut reader: R,
+        category: Option<MediaCategory>,
+    ) -> Result<MediaId> {
+        let mut media = Vec::new();
+        reader.read_to_end(&mut media)?;
+        let mut query = Vec::new();
+        if let Some(category) = category {
+            query = http::params(&Category {
+                media_category: category,
+            });
+        }
+        let body = Body::Multipart(vec![("media".to_string(), media)]);
+        let upload: Upload = self.call(&Request::upload("media/upload", query, body))?;
+        Ok(upload.media_id)
+    }
+
+    /// Uploads `reader` in chunks, then waits for Twitter to process it.
+    ///
+    /// A reader shorter or longer than `request.total_bytes` fails with
+    /// [`Error::InvalidRequest`] before the upload is finalized.
+    pub fn upload_media_chunked<R: Read>(
+        &self,
+        reader: R,
+        request: &UploadRequest,
+    ) -> Result<MediaId> {
+        check_chunk_size(request.chunk_size)?;
+        let mut upload = self.init_upload(request)?;
+        let mut reader = reader.take(request.total_bytes);
+        let sent = self.append_all(&mut upload, &mut reader, request.chunk_size)?;
+        if sent < request.total_bytes {
+            return Err(Error::InvalidRequest(format!(
+                "the media is {} bytes, but total_bytes is {}",
+                sent, request.total_bytes
+            )));
+        }
+        if read_chunk(reader.get_mut(), &mut [0])? > 0 {
+            return Err(Error::InvalidRequest(format!(
+                "the media is over total_bytes, {}",
+                request.total_bytes
+            )));
+        }
+        let finalized = self.finalize_upload(upload)?;
+        self.wait_for_upload(finalized)
+    }
+
+    /// Starts a chunked upload.
+    pub fn init_upload(&self, request: &UploadRequest) -> Result<ChunkedUpload> {
+        let mut form = command("INIT");
+        form.extend(http::params(request));
+        let upload: Upload = self.call(&Request::upload(
+            "media/upload",
+            Vec::new(),
+            Body::Form(form),
+        ))?;
+        Ok(ChunkedUpload {
+            media_id: upload.media_id,
+            segment: 0,
+        })
+    }
+
+    /// Sends the next chunk of `upload`.
+    ///
+    /// The segment index only advances once the chunk is accepted, so a
+    /// failed chunk can be sent again.
+    pub fn append_upload(&self, upload: &mut ChunkedUpload, chunk: &[u8]) -> Result<()> {
+        let mut query = command("APPEND");
+        query.push(("media_id".to_string(), upload.media_id.to_string()));
+        query.push(("segment_index".to_string(), upload.segment.to_string()));
+        let body = Body::Multipart(vec![("media".to_string(), chunk.to_vec())]);
+        self.send(&Request::upload("media/upload", query, body))?;
+        upload.segment += 1;
+        Ok(())
+    }
+
+    /// Sends the rest of `reader` in chunks of `chunk_size`, from 1 byte to
+    /// [`MAX_CHUNK_SIZE`], returning how many bytes were sent.
+    ///
+    /// After a failure, `upload.segment` is the chunk to resume from:
+    /// position the source at `segment * chunk_size` and call this again.
+    pub fn append_all<R: Read>(
+        &self,
+        upload: &mut ChunkedUpload,
+        mut reader: R,
+        chunk_size: usize,
+    ) -> Result<u64> {
+        check_chunk_size(chunk_size)?;
+        let mut chunk = vec![0; chunk_size];
+        let mut sent = 0;
+        loop {
+            let len = read_chunk(&mut reader, &mut chunk)?;
+            if len == 0 {
+                return Ok(sent);
+            }
+            self.append_upload(upload, &chunk[..len])?;
+            sent += len as u64;
+        }
+    }
+
+    /// Completes a chunked upload. GIFs and videos may still be processing,
+    /// see [`Twitter::wait_for_upload`].
+    pub fn finalize_upload(&self, upload: ChunkedUpload) -> Result<Upload> {
+        let mut form = command("FINALIZE");
+        form.push(("media_id".to_string(), upload.media_id.to_string()));
+        self.call(&Request::upload(
+            "media/upload",
+            Vec::new(),
+            Body::Form(form),
+        ))
+    }
+
+    /// Checks on the processing of a finalized upload.
+    pub fn upload_status(&self, id: MediaId) -> Result<Upload> {
+        let mut query = command("STATUS");
+        query.push(("media_id".to_string(), id.to_string()));
+        self.call(&Request {
+            method: Method::Get,
+            url: format!("{}/media/upload.json", UPLOAD_URL),
+            query,
+            body: Body::Empty,
+        })
+    }
+
+    /// Polls the status of `upload` as often as Twitter asks until it is
+    /// processed, waiting at least a second between checks.
+    ///
+    /// Fails with [`Error::MediaProcessingTimedOut`] if it is still
+    /// processing after [`MAX_PROCESSING_WAIT`].
+    pub fn wait_for_upload(&self, mut upload: Upload) -> Result<MediaId> {
+        let mut waited = Duration::from_secs(0);
+        loop {
+            let info = match upload.processing_info {
+                Some(info) => info,
+                None => return Ok(upload.media_id),
+            };
+            match info.state {
+                ProcessingState::Succeeded => return Ok(upload.media_id),
+                ProcessingState::Failed => {
+                    return Err(Error::MediaProcessing(info.error.unwrap_or_default()))
+                }
+                ProcessingState::Pending | ProcessingState::InProgress => {
+                    let wait = Duration::from_secs(info.check_after_secs.unwrap_or(1).max(1));
+                    waited += wait;
+                    if waited > MAX_PROCESSING_WAIT {
+                        return Err(Error::MediaProcessingTimedOut(upload.media_id));
+                    }
+                    self.transport.sleep(wait);
+                    upload = self.upload_status(upload.media_id)?;
+                }
+            }
+        }
+    }
+
+    /// Sets the alt text of an uploaded photo or GIF, up to
+    /// [`MAX_ALT_TEXT_LENGTH`] characters; longer text fails with
+    /// [`Error::InvalidRequest`] without being sent.
+    ///
+    /// [Docs on Twitter](https://developer.twitter.com/en/docs/media/upload-media/api-reference/post-media-metadata-create)
+    pub fn create_media_metadata(&self, id: MediaId, alt_text: &str) -> Result<()> {
+        let length = alt_text.chars().count();
+        if length > MAX_ALT_TEXT_LENGTH {
+            return Err(Error::InvalidRequest(format!(
+                "alt text is {} characters, over the limit of {}",
+                length, MAX_ALT_TEXT_LENGTH
+            )));
+        }
+        let body = json!({
+            "media_id": id.to_string(),
+            "alt_text": { "text": alt_text },
+        });
+        self.send(&Request::upload(
+            "media/metadata/create",
+            Vec::new(),
+            Body::Json(body),
+        ))?;
+        Ok(())
+    }
+
+    /// Attaches uploaded captions to an uploaded `video`.
+    ///
+    /// [Docs on Twitter](https://developer.twitter.com/en/docs/media/upload-media/api-reference/post-media-subtitles-create)
+    pub fn create_subtitles(&self, video: MediaId, subtitles: &[Subtitle]) -> Result<()> {
+        let subtitles: Vec<_> = subtitles
+            .iter()
+            .map(|subtitle| {
+                json!({
+                    "media_id": subtitle.media_id.to_string(),
+                    "language_code": subtitle.language_code,
+                    "display_name": subtitle.display_name,
+                })
+            })
+            .collect();
+        self.subtitles("media/subtitles/create", video, subtitles)
+    }
+
+    /// Removes the captions in `language_codes` from an uploaded `video`.
+    ///
+    /// [Docs on Twitter](https://developer.twitter.com/en/docs/media/upload-media/api-reference/post-media-subtitles-delete)
+    pub fn delete_subtitles(&self, video: MediaId, language_codes: &[&str]) -> Result<()> {
+        let subtitles: Vec<_> = language_codes
+            .iter()
+            .map(|code| json!({ "language_code": code }))
+            .collect();
+        self.subtitles("media/subtitles/delete", video, subtitles)
+    }
+
+    fn subtitles(
+        &self,
+        path: &str,
+        video: MediaId,
+        subtitles: Vec<serde_json::Value>,
+    ) -> Result<()> {
+        let body = json!({
+            "media_id": video.to_string(),
+            "media_category": "TweetVideo",
+            "subtitle_info": { "subtitles": subtitles },
+        });
+        self.send(&Request::upload(path, Vec::new(), Body::Json(body)))?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct Category {
+    media_category: MediaCategory,
+}
+
+fn command(command: &str) -> Vec<(String, String)> {
+    vec![("command".to_string(), command.to_string())]
+}
+
+fn check_chunk_size(chunk_size: usize) -> Result<()> {
+    if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+        return Err(Error::InvalidRequest(format!(
+            "chunk size {} is not between 1 and {} bytes",
+            chunk_size, MAX_CHUNK_SIZE
+        )));
+    }
+    Ok(())
+}
+
+/// Fills `chunk` as far as `reader` allows, returning the length read.
+fn read_chunk<R: Read>(reader: &mut R, chunk: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < chunk.len() {
+        match reader.read(&mut chunk[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock::Mock;
+
+    /// A query or form parameter of `request`.
+    fn param<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+        let form = match &request.body {
+            Body::Form(form) => &form[..],
+            _ => &[],
+        };
+        request
+            .query
+            .iter()
+            .chain(form)
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn media(request: &Request) -> &[u8] {
+        match &request.body {
+            Body::Multipart(parts) => &parts[0].1,
+            body => panic!("unexpected body {:?}", body),
+        }
+    }
+
+    #[test]
+    fn uploads_photos() {
+        let mock = Mock::default();
+        mock.respond(
+            200,
+            r#"{"media_id":710511363345354753,"media_id_string":"710511363345354753","size":11065,"expires_after_secs":86400,"image":{"image_type":"image/jpeg","w":800,"h":320}}"#,
+        );
+        let twitter = Twitter::new(mock.clone());
+
+        let id = twitter
+            .upload_media(&b"JFIF"[..], Some(MediaCategory::TweetImage))
+            .unwrap();
+        assert_eq!(id, MediaId(710511363345354753));
+
+        let sent = &mock.requests()[0];
+        assert_eq!(sent.url, "https://upload.twitter.com/1.1/media/upload.json");
+        assert_eq!(param(sent, "media_category"), Some("tweet_image"));
+        assert_eq!(media(sent), b"JFIF");
+    }
+
+    #[test]
+    fn uploads_videos_in_chunks() {
+        let mock = Mock::default();
+        mock.respond(
+            202,
+            r#"{"media_id":710511363345354753,"media_id_string":"710511363345354753","expires_after_secs":86399}"#,
+        )
+        .respond(204, "")
+        .respond(204, "")
+        .respond(204, "")
+        .respond(
+            201,
+            r#"{"media_id":710511363345354753,"media_id_string":"710511363345354753","size":10,"expires_after_secs":86400,"processing_info":{"state":"pending","check_after_secs":5}}"#,
+        )
+        .respond(
+            200,
+            r#"{"media_id":710511363345354753,"media_id_string":"710511363345354753","processing_info":{"state":"in_progress","check_after_secs":10,"progress_percent":8}}"#,
+        )
+        .respond(
+            200,
+            r#"{"media_id":710511363345354753,"media_id_string":"710511363345354753","processing_info":{"state":"succeeded","progress_percent":100}}"#,
+        );
+        let twitter = Twitter::new(mock.clone());
+
+        let request = UploadRequest {
+            media_category: Some(MediaCategory::TweetVideo),
+            chunk_size: 4,
+            ..UploadRequest::new("video/mp4", 10)
+        };
+        let id = twitter
+            .upload_media_chunked(&b"0123456789"[..], &request)
+            .unwrap();
+        assert_eq!(id, MediaId(710511363345354753));
+        assert_eq!(
+            mock.sleeps(),
+            vec![Duration::from_secs(5), Duration::from_secs(10)]
+        );
+
+        let sent = mock.requests();
+        let commands: Vec<_> = sent
+            .iter()
+            .map(|request| param(request, "command").unwrap())
+            .collect();
+        assert_eq!(
+            commands,
+            ["INIT", "APPEND", "APPEND", "APPEND", "FINALIZE", "STATUS", "STATUS"]
+        );
+        assert_eq!(param(&sent[0], "media_category"), Some("tweet_video"));
+        assert_eq!(param(&sent[0], "total_bytes"), Some("10"));
+        assert_eq!(param(&sent[3], "segment_index"), Some("2"));
+        assert_eq!(media(&sent[3]), b"89");
+        assert_eq!(sent[5].method, Method::Get);
+    }
+
+    #[test]
+    fn chunk_sizes_are_checked() {
+        let mock = Mock::default();
+        let twitter = Twitter::new(mock.clone());
+
+        for chunk_size in [0, MAX_CHUNK_SIZE + 1] {
+            let request = UploadRequest {
+                chunk_size,
+                ..UploadRequest::new("video/mp4", 10)
+            };
+            let result = twitter.upload_media_chunked(&b"0123456789"[..], &request);
+            assert!(matches!(result, Err(Error::InvalidRequest(_))));
+
+            let mut upload = ChunkedUpload {
+                media_id: MediaId(1),
+                segment: 0,
+            };
+            let result = twitter.append_all(&mut upload, &b"0123456789"[..], chunk_size);
+            assert!(matches!(result, Err(Error::InvalidRequest(_))));
+        }
+        assert!(mock.requests().is_empty());
+    }
+
+    #[test]
+    fn media_sizes_are_checked_before_finalizing() {
+        let init = r#"{"media_id":1,"media_id_string":"1","expires_after_secs":86399}"#;
+        let mock = Mock::default();
+        mock.respond(202, init)
+            .respond(204, "")
+            .respond(204, "")
+            .respond(202, init)
+            .respond(204, "")
+            .respond(204, "")
+            .respond(204, "");
+        let twitter = Twitter::new(mock.clone());
+        let request = UploadRequest {
+            chunk_size: 4,
+            ..UploadRequest::new("video/mp4", 10)
+        };
+
+        for media in [&b"01234567"[..], &b"0123456789!"[..]] {
+            let result = twitter.upload_media_chunked(media, &request);
+            assert!(matches!(result, Err(Error::InvalidRequest(_))));
+        }
+        let sent = mock.requests();
+        let commands: Vec<_> = sent
+            .iter()
+            .map(|request| param(request, "command").unwrap())
+            .collect();
+        assert_eq!(
+            commands,
+            ["INIT", "APPEND", "APPEND", "INIT", "APPEND", "APPEND", "APPEND"]
+        );
+        // Nothing past total_bytes is sent.
+        assert_eq!(media(&sent[6]), b"89");
+    }
+
+    #[test]
+    fn deserialized_requests_get_the_default_chunk_size() {
+        let request: UploadRequest =
+            serde_json::from_str(r#"{"media_type":"video/mp4","total_bytes":10,"media_category":null,"additional_owners":null}"#)
+                .unwrap();
+        assert_eq!(request, UploadRequest::new("video/mp4", 10));
+    }
+
+    #[test]
+    fn failed_chunks_can_be_resent() {
+        let mock = Mock::default();
+        mock.respond(
+            503,
+            r#"{"errors":[{"code":130,"message":"Over capacity"}]}"#,
+        )
+        .respond(204, "");
+        let twitter = Twitter::new(mock.clone());
+
+        let mut upload = ChunkedUpload {
+            media_id: MediaId(1),
+            segment: 3,
+        };
+        assert!(twitter.append_upload(&mut upload, b"data").is_err());
+        assert_eq!(upload.segment, 3);
+        twitter.append_upload(&mut upload, b"data").unwrap();
+        assert_eq!(upload.segment, 4);
+    }
+
+    #[test]
+    fn processing_failures_are_errors() {
+        let mock = Mock::default();
+        mock.respond(
+            200,
+            r#"{"media_id":1,"media_id_string":"1","processing_info":{"state":"failed","progress_percent":99,"error":{"code":1,"name":"InvalidMedia","message":"Unsupported video format"}}}"#,
+        );
+        let twitter = Twitter::new(mock);
+
+        match twitter
+            .upload_status(MediaId(1))
+            .and_then(|upload| twitter.wait_for_upload(upload))
+        {
+            Err(Error::MediaProcessing(error)) => assert_eq!(error.name, "InvalidMedia"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn processing_waits_are_bounded() {
+        let mock = Mock::default();
+        let in_progress = r#"{"media_id":1,"media_id_string":"1","processing_info":{"state":"in_progress","check_after_secs":240}}"#;
+        mock.respond(200, in_progress)
+            .respond(200, in_progress)
+            .respond(200, in_progress);
+        let twitter = Twitter::new(mock.clone());
+
+        match twitter
+            .upload_status(MediaId(1))
+            .and_then(|upload| twitter.wait_for_upload(upload))
+        {
+            Err(Error::MediaProcessingTimedOut(id)) => assert_eq!(id, MediaId(1)),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(mock.sleeps(), vec![Duration::from_secs(240); 2]);
+        assert_eq!(mock.requests().len(), 3);
+    }
+
+    #[test]
+    fn alt_text_length_is_checked() {
+        let mock = Mock::default();
+        mock.respond(200, "");
+        let twitter = Twitter::new(mock.clone());
+
+        let alt_text = "é".repeat(MAX_ALT_TEXT_LENGTH + 1);
+        let result = twitter.create_media_metadata(MediaId(1), &alt_text);
+        assert!(matches!(result, Err(Error::InvalidRequest(_))));
+        assert!(mock.requests().is_empty());
+
+        // Characters are counted, not bytes.
+        let alt_text = "é".repeat(MAX_ALT_TEXT_LENGTH);
+        twitter
+            .create_media_metadata(MediaId(1), &alt_text)
+            .unwrap();
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[test]
+    fn describes_and_captions_media() {
+        let mock = Mock::default();
+        mock.respond(200, "").respond(200, "").respond(200, "");
+        let twitter = Twitter::new(mock.clone());
+
+        twitter
+            .create_media_metadata(MediaId(1), "A dog catching a frisbee.")
+            .unwrap();
+        let subtitles = [Subtitle {
+            media_id: MediaId(3),
+            language_code: "EN".to_string(),
+            display_name: "English".to_string(),
+        }];
+        twitter.create_subtitles(MediaId(2), &subtitles).unwrap();
+        twitter.delete_subtitles(MediaId(2), &["EN"]).unwrap();
+
+        let bodies: Vec<_> = mock
+            .requests()
+            .into_iter()
+            .map(|request| match request.body {
+                Body::Json(body) => (request.url, body),
+                body => panic!("unexpected body {:?}", body),
+            })
+            .collect();
+        assert_eq!(
+            bodies[0].0,
+            "https://upload.twitter.com/1.1/media/metadata/create.json"
+        );
+        assert_eq!(
+            bodies[0].1,
+            json!({"media_id": "1", "alt_text": {"text": "A dog catching a frisbee."}})
+        );
+        assert_eq!(
+            bodies[1].1["subtitle_info"]["subtitles"][0],
+            json!({"media_id": "3", "language_code": "EN", "display_name": "English"})
+        );
+        assert!(bodies[2].0.ends_with("/media/subtitles/delete.json"));
+        assert_eq!(
+            bodies[2].1,
+            json!({
+                "media_id": "2",
+                "media_category": "TweetVideo",
+                "subtitle_info": {"subtitles": [{"language_code": "EN"}]},
+            })
+        );
+    }
+}
@@ -0,0 +1,470 @@
+//! Premium and Enterprise search, over the last 30 days or the full archive.
+//!
+//! Searches run against an [`Environment`] set up in the developer portal.
+//! Results come a page at a time: pass the `next` token of a response back
+//! in the request to get the following page.
+
+use crate::api::Tweet;
+use crate::http::{Body, Method, Request, API_URL};
+use crate::{Result, Twitter};
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Which tweets an environment searches.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Product {
+    /// Tweets from the last 30 days.
+    ThirtyDay,
+    /// Tweets since 2006.
+    FullArchive,
+}
+
+impl Product {
+    fn name(self) -> &'static str {
+        match self {
+            Product::ThirtyDay => "30day",
+            Product::FullArchive => "fullarchive",
+        }
+    }
+}
+
+/// A search environment, identified by its product and label.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Environment {
+    /// The search endpoint without `.json`.
+    url: String,
+}
+
+impl Environment {
+    /// A premium environment, like the 30-day one labelled `dev`.
+    pub fn premium(product: Product, label: &str) -> Environment {
+        Environment {
+            url: format!("{}/tweets/search/{}/{}", API_URL, product.name(), label),
+        }
+    }
+
+    /// An Enterprise environment of the account `account`.
+    pub fn enterprise(product: Product, account: &str, label: &str) -> Environment {
+        Environment {
+            url: format!(
+                "https://gnip-api.twitter.com/search/{}/accounts/{}/{}",
+                product.name(),
+                account,
+                label
+            ),
+        }
+    }
+
+    fn request<T: serde::Serialize>(&self, endpoint: &str, body: &T) -> Request {
+        Request {
+            method: Method::Post,
+            url: format!("{}{}.json", self.url, endpoint),
+            query: Vec::new(),
+            body: Body::Json(serde_json::to_value(body).unwrap()),
+        }
+    }
+}
+
+/// A minute in UTC to search from or to, written `yyyymmddhhmm`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SearchTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+}
+
+impl SearchTime {
+    /// Fails unless the date exists and the time is between 00:00 and 23:59.
+    pub fn new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+    ) -> std::result::Result<SearchTime, SearchTimeError> {
+        let leap =
+            year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+        let days = match month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            1..=12 => 31,
+            _ => 0,
+        };
+        if year > 9999 || day == 0 || day > days || hour > 23 || minute > 59 {
+            return Err(SearchTimeError::OutOfRange);
+        }
+        Ok(SearchTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+        })
+    }
+}
+
+impl FromStr for SearchTime {
+    type Err = SearchTimeError;
+
+    fn from_str(time: &str) -> std::result::Result<SearchTime, SearchTimeError> {
+        if time.len() != 12 || !time.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(SearchTimeError::Format);
+        }
+        let field = |range: std::ops::Range<usize>| time[range].parse().unwrap();
+        SearchTime::new(
+            field(0..4),
+            field(4..6) as u8,
+            field(6..8) as u8,
+            field(8..10) as u8,
+            field(10..12) as u8,
+        )
+    }
+}
+
+impl TryFrom<String> for SearchTime {
+    type Error = SearchTimeError;
+
+    fn try_from(time: String) -> std::result::Result<SearchTime, SearchTimeError> {
+        time.parse()
+    }
+}
+
+impl From<SearchTime> for String {
+    fn from(time: SearchTime) -> String {
+        time.to_string()
+    }
+}
+
+impl fmt::Display for SearchTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}{:02}{:02}{:02}{:02}",
+            self.year, self.month, self.day, self.hour, self.minute
+        )
+    }
+}
+
+/// Why a [`SearchTime`] is invalid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SearchTimeError {
+    /// Not 12 digits.
+    Format,
+    /// No such date or time, like the 30th of February.
+    OutOfRange,
+}
+
+impl fmt::Display for SearchTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SearchTimeError::Format => f.write_str("search time is not yyyymmddhhmm"),
+            SearchTimeError::OutOfRange => f.write_str("search time does not exist"),
+        }
+    }
+}
+
+impl error::Error for SearchTimeError {}
+
+/// Premium search request
+///
+/// # Resource Information
+///
+/// Response formats: **JSON**
+///
+/// Requires authentication?: **Yes**
+///
+/// Rate limited?: **Yes**
+///
+/// Requests / minute: **30** (Sandbox), **60** (Premium)
+///
+/// [Docs on Twitter](https://developer.twitter.com/en/docs/tweets/search/api-reference/premium-search)
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PremiumSearchRequest {
+    /// A rule in the premium operator syntax, like `#rust has:media`.
+    pub query: String,
+
+    /// Oldest time to search from. Defaults to 30 days ago.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_date: Option<SearchTime>,
+
+    /// Time to search up to, not including it. Defaults to now.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_date: Option<SearchTime>,
+
+    /// Tweets per page, from 10 to 100 in the sandbox and up to 500 with
+    /// paid access.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_results: Option<u16>,
+
+    /// The `next` token of the previous page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+}
+
+impl PremiumSearchRequest {
+    pub fn new<S: Into<String>>(query: S) -> PremiumSearchRequest {
+        PremiumSearchRequest {
+            query: query.into(),
+            ..PremiumSearchRequest::default()
+        }
+    }
+}
+
+/// Premium search response
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PremiumSearchResponse {
+    /// Newest first.
+    pub results: Vec<Tweet>,
+    /// Token for the next page, missing on the last one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    pub request_parameters: RequestParameters,
+}
+
+/// The effective parameters of a search, with defaults filled in.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestParameters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_results: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<Bucket>,
+    pub from_date: String,
+    pub to_date: String,
+}
+
+/// The period each count of a counts request covers.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bucket {
+    Minute,
+    Hour,
+    Day,
+}
+
+/// Premium search counts request
+///
+/// # Resource Information
+///
+/// Response formats: **JSON**
+///
+/// Requires authentication?: **Yes**
+///
+/// Rate limited?: **Yes**
+///
+/// Requests / minute: **30** (Sandbox), **60** (Premium)
+///
+/// [Docs on Twitter](https://developer.twitter.com/en/docs/tweets/search/api-reference/premium-search#CountsEndpoint)
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CountsRequest {
+    pub query: String,
+
+    /// As in [`PremiumSearchRequest::from_date`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_date: Option<SearchTime>,
+
+    /// As in [`PremiumSearchRequest::to_date`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_date: Option<SearchTime>,
+
+    /// Defaults to hourly counts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<Bucket>,
+
+    /// The `next` token of the previous page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+}
+
+impl CountsRequest {
+    pub fn new<S: Into<String>>(query: S) -> CountsRequest {
+        CountsRequest {
+            query: query.into(),
+            ..CountsRequest::default()
+        }
+    }
+}
+
+/// Premium search counts response
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CountsResponse {
+    pub results: Vec<Count>,
+    /// Total of the counts on this page.
+    pub total_count: u64,
+    /// Token for the next page, missing on the last one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    pub request_parameters: RequestParameters,
+}
+
+/// The number of matching tweets in one bucket.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Count {
+    /// Start of the bucket, in UTC as `yyyymmddhhmm`.
+    pub time_period: String,
+    pub count: u64,
+}
+
+impl Twitter {
+    /// Searches the tweets of `environment`.
+    pub fn premium_search(
+        &self,
+        environment: &Environment,
+        request: &PremiumSearchRequest,
+    ) -> Result<PremiumSearchResponse> {
+        self.call(&environment.request("", request))
+    }
+
+    /// Counts the tweets of `environment` matching a query over time.
+    pub fn premium_counts(
+        &self,
+        environment: &Environment,
+        request: &CountsRequest,
+    ) -> Result<CountsResponse> {
+        self.call(&environment.request("/counts", request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::MatchingRule;
+    use crate::http::mock::Mock;
+    use serde_json::{json, Value};
+
+    fn body(request: &Request) -> &Value {
+        match &request.body {
+            Body::Json(body) => body,
+            body => panic!("unexpected body {:?}", body),
+        }
+    }
+
+    #[test]
+    fn searches_with_next_tokens() {
+        let mut tweet: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/tweets/plain_text.json")).unwrap();
+        tweet["matching_rules"] = json!([{"tag": "rustaceans", "id": 1166916266197536768u64}]);
+        let first = json!({
+            "results": [tweet],
+            "next": "eyJtYXhJZCI6ODUwMDA2MjQ1MTIxNjk1NzQ0fQ==",
+            "requestParameters": {"maxResults": 10, "fromDate": "201903010000", "toDate": "201903080000"},
+        });
+        let last = json!({
+            "results": [],
+            "requestParameters": {"maxResults": 10, "fromDate": "201903010000", "toDate": "201903080000"},
+        });
+        let mock = Mock::default();
+        mock.respond(200, &first.to_string())
+            .respond(200, &last.to_string());
+        let twitter = Twitter::new(mock.clone());
+
+        let environment = Environment::premium(Product::FullArchive, "research");
+        let mut request = PremiumSearchRequest {
+            from_date: Some("201903010000".parse().unwrap()),
+            to_date: Some(SearchTime::new(2019, 3, 8, 0, 0).unwrap()),
+            max_results: Some(10),
+            ..PremiumSearchRequest::new("#rust has:media")
+        };
+        let page = twitter.premium_search(&environment, &request).unwrap();
+        assert_eq!(
+            page.results[0].matching_rules,
+            Some(vec![MatchingRule {
+                tag: Some("rustaceans".to_string()),
+                id: 1166916266197536768,
+                #[cfg(feature = "extra-fields")]
+                extra: serde_json::Map::new(),
+            }])
+        );
+        request.next = page.next;
+        let page = twitter.premium_search(&environment, &request).unwrap();
+        assert_eq!(page.next, None);
+
+        let sent = mock.requests();
+        assert_eq!(
+            sent[0].url,
+            "https://api.twitter.com/1.1/tweets/search/fullarchive/research.json"
+        );
+        assert_eq!(
+            body(&sent[0]),
+            &json!({
+                "query": "#rust has:media",
+                "fromDate": "201903010000",
+                "toDate": "201903080000",
+                "maxResults": 10,
+            })
+        );
+        assert_eq!(
+            body(&sent[1])["next"],
+            "eyJtYXhJZCI6ODUwMDA2MjQ1MTIxNjk1NzQ0fQ=="
+        );
+    }
+
+    #[test]
+    fn counts_in_buckets() {
+        let mock = Mock::default();
+        mock.respond(
+            200,
+            r#"{"results":[{"timePeriod":"201903010000","count":12},{"timePeriod":"201903020000","count":30}],"totalCount":42,"requestParameters":{"bucket":"day","fromDate":"201903010000","toDate":"201903030000"}}"#,
+        );
+        let twitter = Twitter::new(mock.clone());
+
+        let environment = Environment::enterprise(Product::ThirtyDay, "acme", "prod");
+        let request = CountsRequest {
+            bucket: Some(Bucket::Day),
+            ..CountsRequest::new("from:TwitterDev")
+        };
+        let counts = twitter.premium_counts(&environment, &request).unwrap();
+        assert_eq!(counts.total_count, 42);
+        assert_eq!(counts.results[1].time_period, "201903020000");
+        assert_eq!(counts.request_parameters.bucket, Some(Bucket::Day));
+
+        let sent = &mock.requests()[0];
+        assert_eq!(
+            sent.url,
+            "https://gnip-api.twitter.com/search/30day/accounts/acme/prod/counts.json"
+        );
+        assert_eq!(
+            body(sent),
+            &json!({"query": "from:TwitterDev", "bucket": "day"})
+        );
+    }
+
+    #[test]
+    fn checks_search_times() {
+        let time: SearchTime = "202002291530".parse().unwrap();
+        assert_eq!(time, SearchTime::new(2020, 2, 29, 15, 30).unwrap());
+        assert_eq!(time.to_string(), "202002291530");
+        assert_eq!(serde_json::to_value(time).unwrap(), json!("202002291530"));
+
+        for invalid in ["20200229153", "2020-02-29T1", "20200229153a"] {
+            assert_eq!(invalid.parse::<SearchTime>(), Err(SearchTimeError::Format));
+        }
+        for invalid in [
+            "201902291530",
+            "202013011530",
+            "202004310000",
+            "202002292400",
+        ] {
+            assert_eq!(
+                invalid.parse::<SearchTime>(),
+                Err(SearchTimeError::OutOfRange)
+            );
+        }
+        assert!(serde_json::from_value::<CountsRequest>(json!({
+            "query": "from:TwitterDev",
+            "fromDate": "201902300000",
+        }))
+        .is_err());
+    }
+}
@@ -0,0 +1,497 @@
+//! Search and filter rules, evaluated locally.
+//!
+//! [`Rule`] parses the operator syntax shared by standard search, premium
+//! search and filtered streams, so rules can be tried against archived
+//! tweets without spending any requests:
+//!
+//! ```
+//! use twitter::rule::Rule;
+//!
+//! let rule: Rule = "(#rust OR rustlang) -is:retweet lang:en".parse().unwrap();
+//! ```
+//!
+//! Matching follows Twitter's documented behaviour, but Twitter's own
+//! tokenization is not public, so borderline keyword matches may differ.
+
+use crate::api::Tweet;
+use crate::text;
+use std::error;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::FromStr;
+
+/// A parsed rule. Text is kept lowercase, as matching ignores case.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Rule {
+    /// A word, or several words next to each other like `don't`.
+    Keyword(String),
+    /// Words next to each other, in order.
+    Phrase(String),
+    /// Tweets by a user, given by screen name or ID.
+    From(String),
+    /// Replies to a user, given by screen name or ID.
+    To(String),
+    /// `@` mentions of a screen name.
+    Mention(String),
+    Hashtag(String),
+    Cashtag(String),
+    Lang(String),
+    /// Links whose URL contains this.
+    Url(String),
+    Has(Has),
+    Is(Is),
+    Not(Box<Rule>),
+    And(Vec<Rule>),
+    Or(Vec<Rule>),
+}
+
+/// What a tweet contains, for `has:`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Has {
+    Media,
+    Images,
+    Videos,
+    /// Links, including to attached media.
+    Links,
+    Mentions,
+    Hashtags,
+    Cashtags,
+    Geo,
+}
+
+/// What kind of tweet it is, for `is:`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Is {
+    Retweet,
+    Reply,
+    Quote,
+}
+
+impl Rule {
+    pub fn parse(rule: &str) -> Result<Rule, ParseError> {
+        let tokens = lex(rule)?;
+        let mut parser = Parser {
+            tokens: tokens.into_iter().peekable(),
+        };
+        let rule = parser.or()?;
+        match parser.tokens.next() {
+            None => Ok(rule),
+            Some(_) => Err(ParseError::UnbalancedParentheses),
+        }
+    }
+
+    /// Whether `tweet` matches.
+    pub fn matches(&self, tweet: &Tweet) -> bool {
+        let entities = &tweet.entities;
+        let media = tweet
+            .extended_entities
+            .as_ref()
+            .map_or(&entities.media, |extended| &extended.media);
+        match self {
+            Rule::Keyword(words) | Rule::Phrase(words) => {
+                let text = words_of(&text::unescape(tweet.body()));
+                let words = words_of(words);
+                !words.is_empty() && text.windows(words.len()).any(|window| window == &words[..])
+            }
+            Rule::From(user) => {
+                user == &tweet.user.id_str || eq(tweet.user.screen_name.as_deref(), user)
+            }
+            Rule::To(user) => {
+                tweet.in_reply_to_user_id_str.as_deref() == Some(user.as_str())
+                    || eq(tweet.in_reply_to_screen_name.as_deref(), user)
+            }
+            Rule::Mention(user) => entities
+                .user_mentions
+                .iter()
+                .any(|mention| eq(Some(&mention.screen_name), user)),
+            Rule::Hashtag(tag) => entities
+                .hashtags
+                .iter()
+                .any(|hashtag| eq(Some(&hashtag.text), tag)),
+            Rule::Cashtag(tag) => entities
+                .symbols
+                .iter()
+                .flatten()
+                .any(|symbol| eq(Some(&symbol.text), tag)),
+            Rule::Lang(lang) => tweet.lang.as_deref() == Some(lang.as_str()),
+            Rule::Url(part) => entities
+                .urls
+                .iter()
+                .map(|url| url.expanded_url.as_deref().unwrap_or(&url.url))
+                .chain(media.iter().map(|media| media.expanded_url.as_str()))
+                .any(|url| url.to_lowercase().contains(part.as_str())),
+            Rule::Has(Has::Media) => !media.is_empty(),
+            Rule::Has(Has::Images) => media.iter().any(|media| media.media_type == "photo"),
+            Rule::Has(Has::Videos) => media.iter().any(|media| media.media_type == "video"),
+            Rule::Has(Has::Links) => !entities.urls.is_empty() || !media.is_empty(),
+            Rule::Has(Has::Mentions) => !entities.user_mentions.is_empty(),
+            Rule::Has(Has::Hashtags) => !entities.hashtags.is_empty(),
+            Rule::Has(Has::Cashtags) => entities.symbols.iter().flatten().next().is_some(),
+            Rule::Has(Has::Geo) => tweet.coordinates.is_some() || tweet.place.is_some(),
+            Rule::Is(Is::Retweet) => tweet.retweeted_status.is_some(),
+            Rule::Is(Is::Reply) => tweet.in_reply_to_status_id.is_some(),
+            Rule::Is(Is::Quote) => tweet.is_quote_status == Some(true),
+            Rule::Not(rule) => !rule.matches(tweet),
+            Rule::And(rules) => rules.iter().all(|rule| rule.matches(tweet)),
+            Rule::Or(rules) => rules.iter().any(|rule| rule.matches(tweet)),
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = ParseError;
+
+    fn from_str(rule: &str) -> Result<Rule, ParseError> {
+        Rule::parse(rule)
+    }
+}
+
+/// Why a rule could not be parsed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseError {
+    /// A rule, group or side of an `OR` with nothing in it.
+    MissingTerm,
+    UnterminatedQuote,
+    UnbalancedParentheses,
+    UnknownOperator(String),
+    InvalidValue {
+        operator: String,
+        value: String,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::MissingTerm => f.write_str("expected a term"),
+            ParseError::UnterminatedQuote => f.write_str("unterminated quote"),
+            ParseError::UnbalancedParentheses => f.write_str("unbalanced parentheses"),
+            ParseError::UnknownOperator(operator) => write!(f, "unknown operator {}:", operator),
+            ParseError::InvalidValue { operator, value } => {
+                write!(f, "invalid value for {}: {}", operator, value)
+            }
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Or,
+    And,
+    Not,
+    Phrase(String),
+    /// A word or `operator:value`, with the value unquoted.
+    Word(String),
+}
+
+fn lex(rule: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut rest = rule;
+    loop {
+        rest = rest.trim_start();
+        let c = match rest.chars().next() {
+            Some(c) => c,
+            None => return Ok(tokens),
+        };
+        let token = match c {
+            '(' | ')' | '-' => {
+                rest = &rest[1..];
+                match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Not,
+                }
+            }
+            '"' => {
+                let (phrase, after) = quoted(&rest[1..])?;
+                rest = after;
+                Token::Phrase(phrase.to_string())
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || "()\"".contains(c))
+                    .unwrap_or(rest.len());
+                let mut word = rest[..end].to_string();
+                rest = &rest[end..];
+                // Operator values may be quoted, like `url:"https://..."`.
+                if word.ends_with(':') && rest.starts_with('"') {
+                    let (value, after) = quoted(&rest[1..])?;
+                    word.push_str(value);
+                    rest = after;
+                }
+                match word.as_str() {
+                    "OR" => Token::Or,
+                    "AND" => Token::And,
+                    "NOT" => Token::Not,
+                    _ => Token::Word(word),
+                }
+            }
+        };
+        tokens.push(token);
+    }
+}
+
+/// Splits `rest`, just after an opening quote, at the closing one.
+fn quoted(rest: &str) -> Result<(&str, &str), ParseError> {
+    let end = rest.find('"').ok_or(ParseError::UnterminatedQuote)?;
+    Ok((&rest[..end], &rest[end + 1..]))
+}
+
+/// Recursive descent over the tokens. Juxtaposition, or `AND`, binds
+/// tighter than `OR`.
+struct Parser {
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser {
+    fn or(&mut self) -> Result<Rule, ParseError> {
+        let mut rules = vec![self.and()?];
+        while self.tokens.next_if_eq(&Token::Or).is_some() {
+            rules.push(self.and()?);
+        }
+        Ok(one_or(rules, Rule::Or))
+    }
+
+    fn and(&mut self) -> Result<Rule, ParseError> {
+        let mut rules = Vec::new();
+        loop {
+            match self.tokens.peek() {
+                None | Some(Token::Close) | Some(Token::Or) => break,
+                Some(Token::And) => {
+                    self.tokens.next();
+                }
+                Some(_) => rules.push(self.unary()?),
+            }
+        }
+        if rules.is_empty() {
+            return Err(ParseError::MissingTerm);
+        }
+        Ok(one_or(rules, Rule::And))
+    }
+
+    fn unary(&mut self) -> Result<Rule, ParseError> {
+        match self.tokens.next() {
+            Some(Token::Not) => match self.tokens.peek() {
+                None | Some(Token::Close) | Some(Token::Or) | Some(Token::And) => {
+                    Err(ParseError::MissingTerm)
+                }
+                Some(_) => Ok(Rule::Not(Box::new(self.unary()?))),
+            },
+            Some(Token::Open) => {
+                let rule = self.or()?;
+                match self.tokens.next() {
+                    Some(Token::Close) => Ok(rule),
+                    _ => Err(ParseError::UnbalancedParentheses),
+                }
+            }
+            Some(Token::Phrase(phrase)) => Ok(Rule::Phrase(phrase.to_lowercase())),
+            Some(Token::Word(word)) => term(&word),
+            _ => Err(ParseError::MissingTerm),
+        }
+    }
+}
+
+fn one_or(mut rules: Vec<Rule>, combine: fn(Vec<Rule>) -> Rule) -> Rule {
+    if rules.len() == 1 {
+        rules.pop().unwrap()
+    } else {
+        combine(rules)
+    }
+}
+
+/// A word, sign-prefixed entity or `operator:value`.
+fn term(word: &str) -> Result<Rule, ParseError> {
+    let lower = word.to_lowercase();
+    let (operator, value) = match lower.find(':') {
+        Some(colon) if colon > 0 => (&lower[..colon], &lower[colon + 1..]),
+        _ => return Ok(sign_term(&lower)),
+    };
+    let invalid = || ParseError::InvalidValue {
+        operator: operator.to_string(),
+        value: value.to_string(),
+    };
+    if value.is_empty() && !matches!(operator, "http" | "https") {
+        return Err(invalid());
+    }
+    let value = value.to_string();
+    Ok(match operator {
+        "from" => Rule::From(value.trim_start_matches('@').to_string()),
+        "to" => Rule::To(value.trim_start_matches('@').to_string()),
+        "lang" => Rule::Lang(value),
+        "url" => Rule::Url(value),
+        "has" => Rule::Has(match value.as_str() {
+            "media" => Has::Media,
+            "images" => Has::Images,
+            "videos" | "video_link" => Has::Videos,
+            "links" => Has::Links,
+            "mentions" => Has::Mentions,
+            "hashtags" => Has::Hashtags,
+            "symbols" | "cashtags" => Has::Cashtags,
+            "geo" => Has::Geo,
+            _ => return Err(invalid()),
+        }),
+        "is" => Rule::Is(match value.as_str() {
+            "retweet" => Is::Retweet,
+            "reply" => Is::Reply,
+            "quote" => Is::Quote,
+            _ => return Err(invalid()),
+        }),
+        // The standard search spelling of `has:` and `is:`.
+        "filter" => match value.as_str() {
+            "media" => Rule::Has(Has::Media),
+            "images" => Rule::Has(Has::Images),
+            "native_video" | "videos" => Rule::Has(Has::Videos),
+            "links" => Rule::Has(Has::Links),
+            "retweets" | "nativeretweets" => Rule::Is(Is::Retweet),
+            "replies" => Rule::Is(Is::Reply),
+            "quote" => Rule::Is(Is::Quote),
+            _ => return Err(invalid()),
+        },
+        // A bare link, matched as words like Twitter does.
+        "http" | "https" => Rule::Keyword(lower),
+        _ => return Err(ParseError::UnknownOperator(operator.to_string())),
+    })
+}
+
+fn sign_term(word: &str) -> Rule {
+    let mut chars = word.chars();
+    let sign = chars.next();
+    let rest = chars.as_str().to_string();
+    match sign {
+        Some('#') | Some('＃') if !rest.is_empty() => Rule::Hashtag(rest),
+        Some('@') | Some('＠') if !rest.is_empty() => Rule::Mention(rest),
+        Some('$') if !rest.is_empty() => Rule::Cashtag(rest),
+        _ => Rule::Keyword(word.to_string()),
+    }
+}
+
+/// Lowercase words of `text`, splitting at anything but letters, numbers
+/// and underscores.
+fn words_of(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn eq(name: Option<&str>, lowercase: &str) -> bool {
+    name.is_some_and(|name| name.to_lowercase() == lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn fixture(name: &str) -> Tweet {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/tweets")
+            .join(name);
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn keyword(word: &str) -> Rule {
+        Rule::Keyword(word.to_string())
+    }
+
+    #[test]
+    fn parses_rules() {
+        assert_eq!(
+            Rule::parse("apple OR iphone ipad").unwrap(),
+            Rule::Or(vec![
+                keyword("apple"),
+                Rule::And(vec![keyword("iphone"), keyword("ipad")]),
+            ])
+        );
+        assert_eq!(
+            Rule::parse(r#"(#Rust OR "Hello World") AND NOT from:@TwitterDev -is:retweet url:"example.com/a b""#)
+                .unwrap(),
+            Rule::And(vec![
+                Rule::Or(vec![
+                    Rule::Hashtag("rust".to_string()),
+                    Rule::Phrase("hello world".to_string()),
+                ]),
+                Rule::Not(Box::new(Rule::From("twitterdev".to_string()))),
+                Rule::Not(Box::new(Rule::Is(Is::Retweet))),
+                Rule::Url("example.com/a b".to_string()),
+            ])
+        );
+        assert_eq!(
+            Rule::parse("$TWTR filter:links https://t.co/x").unwrap(),
+            Rule::And(vec![
+                Rule::Cashtag("twtr".to_string()),
+                Rule::Has(Has::Links),
+                keyword("https://t.co/x"),
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert_eq!(Rule::parse("  "), Err(ParseError::MissingTerm));
+        assert_eq!(Rule::parse("cats OR"), Err(ParseError::MissingTerm));
+        assert_eq!(Rule::parse("cats -"), Err(ParseError::MissingTerm));
+        assert_eq!(Rule::parse("(cats"), Err(ParseError::UnbalancedParentheses));
+        assert_eq!(Rule::parse("cats)"), Err(ParseError::UnbalancedParentheses));
+        assert_eq!(Rule::parse("\"cats"), Err(ParseError::UnterminatedQuote));
+        assert_eq!(
+            Rule::parse("near:london"),
+            Err(ParseError::UnknownOperator("near".to_string()))
+        );
+        assert_eq!(
+            Rule::parse("has:cats"),
+            Err(ParseError::InvalidValue {
+                operator: "has".to_string(),
+                value: "cats".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn matches_tweets() {
+        let cases = [
+            ("hashtags_and_cashtags.json", "#twtr $twtr earnings", true),
+            (
+                "hashtags_and_cashtags.json",
+                "\"call starts now\" has:links",
+                true,
+            ),
+            ("hashtags_and_cashtags.json", "\"starts call\"", false),
+            (
+                "hashtags_and_cashtags.json",
+                "url:investor.twitterinc",
+                true,
+            ),
+            (
+                "hashtags_and_cashtags.json",
+                "earnings -has:hashtags",
+                false,
+            ),
+            ("plain_text.json", "from:twitterdev we're", true),
+            ("plain_text.json", "from:2244994945 lang:en", true),
+            ("plain_text.json", "has:media OR is:reply", false),
+            ("reply.json", "to:TwitterDev is:reply @twitterapi", true),
+            ("reply.json", "from:TwitterDev", false),
+            ("retweet.json", "is:retweet (docs OR blog)", true),
+            ("retweet.json", "-filter:retweets", false),
+            ("quote_of_deleted_tweet.json", "is:quote aged", true),
+            ("photo_with_alt_text.json", "has:images sunset", true),
+            ("photo_with_alt_text.json", "has:videos", false),
+        ];
+        for &(name, rule, expected) in &cases {
+            let tweet = fixture(name);
+            assert_eq!(
+                Rule::parse(rule).unwrap().matches(&tweet),
+                expected,
+                "{} on {}",
+                rule,
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,525 @@
+//! Searching recent tweets with the standard search API.
+//!
+//! Queries can be written by hand or composed with [`Query`], which quotes
+//! terms that would otherwise be read as operators.
+
+use crate::api::Tweet;
+use crate::http::{self, Request};
+use crate::timelines::TweetMode;
+use crate::{Error, Result, Twitter};
+use std::error;
+use std::fmt;
+
+/// The longest query the standard search API accepts, in characters.
+pub const MAX_QUERY_LENGTH: usize = 500;
+
+/// Which tweets to prefer when there are more matches than fit in a page.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultType {
+    Mixed,
+    Recent,
+    Popular,
+}
+
+/// Search/tweets request
+///
+/// # Resource Information
+///
+/// Response formats: **JSON**
+///
+/// Requires authentication?: **Yes**
+///
+/// Rate limited?: **Yes**
+///
+/// Requests / 15-min window (user auth): **180**
+///
+/// Requests / 15-min window (app auth): **450**
+///
+/// [Docs on Twitter](https://developer.twitter.com/en/docs/tweets/search/api-reference/get-search-tweets)
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct SearchRequest {
+    /// The query, up to [`MAX_QUERY_LENGTH`] characters, see [`Query`].
+    pub q: String,
+
+    /// Only tweets by users located within a radius of a point, given as
+    /// `latitude,longitude,radius` with a radius in `mi` or `km`, like
+    /// `37.781157,-122.398720,1mi`.
+    pub geocode: Option<String>,
+
+    /// Only tweets in a language, as an ISO 639-1 code.
+    pub lang: Option<String>,
+
+    /// Language of the query, only `ja` is effective.
+    pub locale: Option<String>,
+
+    pub result_type: Option<ResultType>,
+
+    /// Number of tweets per page, up to 100.
+    pub count: Option<u8>,
+
+    /// Only tweets created before this date, given as `YYYY-MM-DD`. The
+    /// search index only goes back about 7 days.
+    pub until: Option<String>,
+
+    /// Only tweets with a greater ID, that is newer than this one.
+    pub since_id: Option<u64>,
+
+    /// Only tweets with an ID less than or equal to this one.
+    pub max_id: Option<u64>,
+
+    /// Set to `false` to leave out `entities`.
+    pub include_entities: Option<bool>,
+
+    pub tweet_mode: Option<TweetMode>,
+}
+
+impl SearchRequest {
+    pub fn new<S: Into<String>>(q: S) -> SearchRequest {
+        SearchRequest {
+            q: q.into(),
+            ..SearchRequest::default()
+        }
+    }
+}
+
+/// Search/tweets response
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SearchResponse {
+    pub statuses: Vec<Tweet>,
+    pub search_metadata: SearchMetadata,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SearchMetadata {
+    /// Seconds the search took.
+    pub completed_in: f64,
+    pub count: u32,
+    pub max_id: u64,
+    pub max_id_str: String,
+    /// Query string for the next, older page. Missing on the last page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_results: Option<String>,
+    pub query: String,
+    /// Query string for tweets newer than this page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_url: Option<String>,
+    pub since_id: u64,
+    pub since_id_str: String,
+}
+
+impl Twitter {
+    /// Searches tweets from the last 7 days or so.
+    ///
+    /// An empty `q`, or one over [`MAX_QUERY_LENGTH`], fails with
+    /// [`Error::InvalidRequest`] without a request.
+    pub fn search(&self, request: &SearchRequest) -> Result<SearchResponse> {
+        check_query(&request.q).map_err(|error| Error::InvalidRequest(error.to_string()))?;
+        self.call(&Request::get("search/tweets", http::params(request)))
+    }
+
+    /// The page after `page` of `request`, or `None` after the last page.
+    ///
+    /// The parameters of `page`'s `next_results` replace those of
+    /// `request`, which keeps the ones Twitter leaves out of it, like
+    /// `tweet_mode`.
+    pub fn search_next(
+        &self,
+        request: &SearchRequest,
+        page: &SearchResponse,
+    ) -> Result<Option<SearchResponse>> {
+        let next = match &page.search_metadata.next_results {
+            Some(next) => next,
+            None => return Ok(None),
+        };
+        check_query(&request.q).map_err(|error| Error::InvalidRequest(error.to_string()))?;
+        let mut query = http::params(request);
+        for (name, value) in http::parse_query(next) {
+            match query.iter_mut().find(|(param, _)| *param == name) {
+                Some(param) => param.1 = value,
+                None => query.push((name, value)),
+            }
+        }
+        self.call(&Request::get("search/tweets", query)).map(Some)
+    }
+}
+
+/// Operators filtering tweets by what they contain, for [`Query::filter`]
+/// and [`Query::exclude`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Filter {
+    /// Any photos, GIFs or videos.
+    Media,
+    Images,
+    NativeVideo,
+    Links,
+    Retweets,
+    Replies,
+    Quote,
+    Verified,
+    /// Leaves out tweets marked possibly sensitive.
+    Safe,
+}
+
+impl Filter {
+    fn name(self) -> &'static str {
+        match self {
+            Filter::Media => "media",
+            Filter::Images => "images",
+            Filter::NativeVideo => "native_video",
+            Filter::Links => "links",
+            Filter::Retweets => "retweets",
+            Filter::Replies => "replies",
+            Filter::Quote => "quote",
+            Filter::Verified => "verified",
+            Filter::Safe => "safe",
+        }
+    }
+}
+
+/// A search query, matching tweets that match all its terms.
+///
+/// Values that cannot be searched for, like a screen name with a space, are
+/// reported by [`Query::build`].
+///
+/// ```
+/// use twitter::search::{Filter, Query};
+///
+/// let query = Query::new()
+///     .phrase("happy hour")
+///     .any(vec![Query::new().from("TwitterDev"), Query::new().hashtag("rust")])
+///     .exclude(Filter::Retweets)
+///     .build()
+///     .unwrap();
+/// assert_eq!(query, r#""happy hour" (from:TwitterDev OR #rust) -filter:retweets"#);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Query {
+    terms: Vec<String>,
+    invalid: Option<QueryError>,
+}
+
+impl Query {
+    pub fn new() -> Query {
+        Query::default()
+    }
+
+    /// Tweets containing `word`. Words that would be read as an operator,
+    /// like `OR` or `from:me`, are quoted.
+    pub fn keyword(self, word: &str) -> Query {
+        let operator = word.starts_with(&['-', '#', '@', '$'][..])
+            || word.contains(|c: char| c.is_whitespace() || "\"():".contains(c))
+            || word == "OR";
+        if operator {
+            self.phrase(word)
+        } else {
+            self.term(word.to_string())
+        }
+    }
+
+    /// Tweets containing the exact `phrase`. Search has no way to escape a
+    /// double quote, so they are left out.
+    pub fn phrase(self, phrase: &str) -> Query {
+        self.term(format!("\"{}\"", phrase.replace('"', "")))
+    }
+
+    /// Tweets sent by the user `screen_name`.
+    pub fn from(self, screen_name: &str) -> Query {
+        self.value("from:", without(screen_name, '@'), is_screen_name)
+    }
+
+    /// Replies to the user `screen_name`.
+    pub fn to(self, screen_name: &str) -> Query {
+        self.value("to:", without(screen_name, '@'), is_screen_name)
+    }
+
+    /// Tweets mentioning the user `screen_name`.
+    pub fn mention(self, screen_name: &str) -> Query {
+        self.value("@", without(screen_name, '@'), is_screen_name)
+    }
+
+    /// Tweets tagged `hashtag`, of letters, digits and underscores.
+    pub fn hashtag(self, hashtag: &str) -> Query {
+        self.value("#", without(hashtag, '#'), |tag| {
+            tag.chars().all(|c| c.is_alphanumeric() || c == '_')
+        })
+    }
+
+    pub fn filter(self, filter: Filter) -> Query {
+        self.term(format!("filter:{}", filter.name()))
+    }
+
+    pub fn exclude(self, filter: Filter) -> Query {
+        self.term(format!("-filter:{}", filter.name()))
+    }
+
+    /// Tweets Twitter detected as written in a language, as an ISO 639-1
+    /// code.
+    pub fn lang(self, code: &str) -> Query {
+        self.value("lang:", code, |code| {
+            code.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+        })
+    }
+
+    /// Tweets sent on or after a day, in UTC.
+    pub fn since(self, year: u16, month: u8, day: u8) -> Query {
+        self.term(format!("since:{:04}-{:02}-{:02}", year, month, day))
+    }
+
+    /// Tweets sent before a day, in UTC.
+    pub fn until(self, year: u16, month: u8, day: u8) -> Query {
+        self.term(format!("until:{:04}-{:02}-{:02}", year, month, day))
+    }
+
+    /// Tweets matching at least one of `queries`.
+    pub fn any<I: IntoIterator<Item = Query>>(mut self, queries: I) -> Query {
+        let queries: Vec<_> = queries.into_iter().collect();
+        if self.invalid.is_none() {
+            self.invalid = queries.iter().find_map(|query| query.invalid.clone());
+        }
+        let alternatives: Vec<_> = queries
+            .into_iter()
+            .filter(|query| !query.terms.is_empty())
+            .map(|query| match query.terms.len() {
+                1 => query.to_string(),
+                _ => format!("({})", query),
+            })
+            .collect();
+        match alternatives.len() {
+            0 => self,
+            1 => self.term(alternatives[0].clone()),
+            _ => self.term(format!("({})", alternatives.join(" OR "))),
+        }
+    }
+
+    /// The query as text, checked against [`MAX_QUERY_LENGTH`].
+    pub fn build(&self) -> std::result::Result<String, QueryError> {
+        if let Some(invalid) = &self.invalid {
+            return Err(invalid.clone());
+        }
+        let query = self.to_string();
+        check_query(&query)?;
+        Ok(query)
+    }
+
+    fn term(mut self, term: String) -> Query {
+        self.terms.push(term);
+        self
+    }
+
+    /// Adds `operator` followed by `value`, if `valid` accepts it.
+    fn value(mut self, operator: &'static str, value: &str, valid: fn(&str) -> bool) -> Query {
+        if !value.is_empty() && valid(value) {
+            self.term(format!("{}{}", operator, value))
+        } else {
+            if self.invalid.is_none() {
+                self.invalid = Some(QueryError::InvalidValue {
+                    operator,
+                    value: value.to_string(),
+                });
+            }
+            self
+        }
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.terms.join(" "))
+    }
+}
+
+fn without(name: &str, sign: char) -> &str {
+    name.trim_start_matches(sign)
+}
+
+/// Screen names are up to 15 letters, digits and underscores.
+fn is_screen_name(name: &str) -> bool {
+    name.len() <= 15 && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn check_query(query: &str) -> std::result::Result<(), QueryError> {
+    let length = query.chars().count();
+    if length == 0 {
+        Err(QueryError::Empty)
+    } else if length > MAX_QUERY_LENGTH {
+        Err(QueryError::TooLong(length))
+    } else {
+        Ok(())
+    }
+}
+
+/// Why a [`Query`] cannot be searched for.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum QueryError {
+    Empty,
+    /// The query has this many characters, over [`MAX_QUERY_LENGTH`].
+    TooLong(usize),
+    /// `value` cannot follow `operator`, like a screen name with a space.
+    InvalidValue {
+        operator: &'static str,
+        value: String,
+    },
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryError::Empty => f.write_str("search query is empty"),
+            QueryError::TooLong(length) => write!(
+                f,
+                "search query is {} characters, over the limit of {}",
+                length, MAX_QUERY_LENGTH
+            ),
+            QueryError::InvalidValue { operator, value } => {
+                write!(f, "{:?} is not a valid value for {}", value, operator)
+            }
+        }
+    }
+}
+
+impl error::Error for QueryError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock::Mock;
+
+    #[test]
+    fn builds_queries() {
+        let query = Query::new()
+            .keyword("rust")
+            .keyword("from:me")
+            .keyword("OR")
+            .phrase(r#"say "hi""#)
+            .mention("@TwitterDev")
+            .to("TwitterAPI")
+            .filter(Filter::Media)
+            .lang("en")
+            .since(2019, 3, 1)
+            .until(2019, 3, 7);
+        assert_eq!(
+            query.build().unwrap(),
+            r#"rust "from:me" "OR" "say hi" @TwitterDev to:TwitterAPI filter:media lang:en since:2019-03-01 until:2019-03-07"#
+        );
+
+        let query = Query::new().any(vec![
+            Query::new().keyword("cats").keyword("dogs"),
+            Query::new(),
+            Query::new().hashtag("#pets"),
+        ]);
+        assert_eq!(query.build().unwrap(), "((cats dogs) OR #pets)");
+        assert_eq!(
+            Query::new()
+                .any(vec![Query::new().keyword("cats")])
+                .to_string(),
+            "cats"
+        );
+    }
+
+    #[test]
+    fn checks_query_length() {
+        assert_eq!(Query::new().build(), Err(QueryError::Empty));
+        let query = Query::new().phrase(&"é".repeat(498));
+        assert_eq!(query.build().map(|q| q.chars().count()), Ok(500));
+        let query = Query::new().phrase(&"é".repeat(499));
+        assert_eq!(query.build(), Err(QueryError::TooLong(501)));
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let invalid = |operator, value: &str| {
+            Err(QueryError::InvalidValue {
+                operator,
+                value: value.to_string(),
+            })
+        };
+        assert_eq!(
+            Query::new().from("a OR b").build(),
+            invalid("from:", "a OR b")
+        );
+        assert_eq!(Query::new().to("@").build(), invalid("to:", ""));
+        assert_eq!(
+            Query::new().mention("\"me\"").build(),
+            invalid("@", "\"me\"")
+        );
+        assert_eq!(
+            Query::new().hashtag("rust -filter:links").build(),
+            invalid("#", "rust -filter:links")
+        );
+        assert_eq!(
+            Query::new().lang("en OR").build(),
+            invalid("lang:", "en OR")
+        );
+        // The first invalid value is reported, also from nested queries.
+        let query = Query::new().keyword("rust").any(vec![
+            Query::new().from("Twitter Dev"),
+            Query::new().lang("e n"),
+        ]);
+        assert_eq!(query.build(), invalid("from:", "Twitter Dev"));
+        assert_eq!(
+            Query::new()
+                .hashtag("#café_2")
+                .lang("zh-cn")
+                .build()
+                .unwrap(),
+            "#café_2 lang:zh-cn"
+        );
+    }
+
+    #[test]
+    fn checks_queries_before_searching() {
+        let mock = Mock::default();
+        let twitter = Twitter::new(mock.clone());
+        for q in [String::new(), "a".repeat(501)] {
+            let result = twitter.search(&SearchRequest::new(q));
+            assert!(matches!(result, Err(Error::InvalidRequest(_))));
+        }
+        assert!(mock.requests().is_empty());
+    }
+
+    #[test]
+    fn pages_through_results() {
+        let tweet = include_str!("../tests/fixtures/tweets/plain_text.json");
+        let first = format!(
+            r#"{{"statuses":[{}],"search_metadata":{{"completed_in":0.047,"max_id":850006245121695744,"max_id_str":"850006245121695744","next_results":"?max_id=850006245121695743&q=%23rust%20filter%3Amedia&count=1&include_entities=1","query":"%23rust+filter%3Amedia","refresh_url":"?since_id=850006245121695744&q=%23rust%20filter%3Amedia&include_entities=1","count":1,"since_id":0,"since_id_str":"0"}}}}"#,
+            tweet
+        );
+        let last = r#"{"statuses":[],"search_metadata":{"completed_in":0.01,"max_id":850006245121695743,"max_id_str":"850006245121695743","query":"%23rust+filter%3Amedia","count":1,"since_id":0,"since_id_str":"0"}}"#;
+        let mock = Mock::default();
+        mock.respond(200, &first).respond(200, last);
+        let twitter = Twitter::new(mock.clone());
+
+        let request = SearchRequest {
+            count: Some(1),
+            lang: Some("en".to_string()),
+            tweet_mode: Some(TweetMode::Extended),
+            ..SearchRequest::new("#rust filter:media")
+        };
+        let page = twitter.search(&request).unwrap();
+        assert_eq!(page.statuses[0].id, 850006245121695744);
+        let page = twitter.search_next(&request, &page).unwrap().unwrap();
+        assert!(page.statuses.is_empty());
+        assert_eq!(twitter.search_next(&request, &page).unwrap(), None);
+
+        let sent = mock.requests();
+        assert_eq!(
+            sent[0].url,
+            "https://api.twitter.com/1.1/search/tweets.json"
+        );
+        let mut query = sent[1].query.clone();
+        query.sort();
+        assert_eq!(
+            query,
+            [
+                ("count", "1"),
+                ("include_entities", "1"),
+                ("lang", "en"),
+                ("max_id", "850006245121695743"),
+                ("q", "#rust filter:media"),
+                ("tweet_mode", "extended"),
+            ]
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+        );
+    }
+}
@@ -0,0 +1,412 @@
+//! Posting and deleting tweets.
+
+use crate::api::Tweet;
+use crate::http::{self, Request};
+use crate::media::MediaId;
+use crate::text::{self, Config, Validation};
+use crate::{Error, Result, Twitter};
+use std::error;
+use std::fmt;
+
+/// Statuses/update request
+///
+/// # Resource Information
+///
+/// Response formats: **JSON**
+///
+/// Requires authentication?: **Yes (user context only)**
+///
+/// Rate limited?: **Yes**
+///
+/// Requests / 3-hour window (user auth): **300**, shared with retweets
+///
+/// [Docs on Twitter](https://developer.twitter.com/en/docs/tweets/post-and-engage/api-reference/post-statuses-update)
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct UpdateRequest {
+    /// The text of the status update.
+    pub status: String,
+
+    /// The ID of an existing status that the update is in reply to. Ignored
+    /// unless the author of that tweet is mentioned in `status`, or
+    /// `auto_populate_reply_metadata` is set.
+    pub in_reply_to_status_id: Option<u64>,
+
+    /// Mention the participants of the replied-to tweet as reply metadata,
+    /// rather than in the text.
+    pub auto_populate_reply_metadata: Option<bool>,
+
+    /// User IDs to leave out of the mentions added by
+    /// `auto_populate_reply_metadata`.
+    pub exclude_reply_user_ids: Option<Vec<u64>>,
+
+    /// A tweet or Direct Message deep link to attach without counting it
+    /// towards the length of `status`.
+    pub attachment_url: Option<String>,
+
+    /// Up to 4 photos, or 1 GIF or video, from `media/upload`.
+    pub media_ids: Option<Vec<MediaId>>,
+
+    /// Whether the media shows content that may be considered sensitive.
+    pub possibly_sensitive: Option<bool>,
+
+    /// Latitude of the location the tweet refers to, from -90 to 90.
+    /// Ignored without `long`.
+    pub lat: Option<f64>,
+
+    /// Longitude of the location the tweet refers to, from -180 to 180.
+    /// Ignored without `lat`.
+    pub long: Option<f64>,
+
+    /// A place from `geo/search`, such as a city or a point of interest.
+    pub place_id: Option<String>,
+
+    /// Whether to put a pin on the exact coordinates the tweet was sent from.
+    pub display_coordinates: Option<bool>,
+}
+
+impl UpdateRequest {
+    /// A plain status update.
+    pub fn new<S: Into<String>>(status: S) -> UpdateRequest {
+        UpdateRequest {
+            status: status.into(),
+            ..UpdateRequest::default()
+        }
+    }
+
+    /// A reply to the tweet `id`, mentioning its participants as reply
+    /// metadata.
+    pub fn reply<S: Into<String>>(status: S, id: u64) -> UpdateRequest {
+        UpdateRequest {
+            in_reply_to_status_id: Some(id),
+            auto_populate_reply_metadata: Some(true),
+            ..UpdateRequest::new(status)
+        }
+    }
+}
+
+/// One tweet of a thread.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ThreadItem {
+    pub text: String,
+    /// Media from `media/upload` to attach, if any.
+    pub media_ids: Vec<MediaId>,
+}
+
+impl<'a> From<&'a str> for ThreadItem {
+    fn from(text: &'a str) -> ThreadItem {
+        ThreadItem {
+            text: text.to_string(),
+            media_ids: Vec::new(),
+        }
+    }
+}
+
+impl From<String> for ThreadItem {
+    fn from(text: String) -> ThreadItem {
+        ThreadItem {
+            text,
+            media_ids: Vec::new(),
+        }
+    }
+}
+
+/// Why a thread was not posted in full.
+#[derive(Debug)]
+pub enum ThreadError {
+    /// Item `index` cannot be posted, so nothing was.
+    Invalid {
+        index: usize,
+        validation: Validation,
+    },
+    /// Posting item `posted.len()` failed after the tweets in `posted` went
+    /// out; resume by replying to the last of them, or delete them.
+    Interrupted { posted: Vec<Tweet>, error: Error },
+}
+
+impl fmt::Display for ThreadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ThreadError::Invalid { index, validation } => {
+                write!(
+                    f,
+                    "tweet {} of the thread is invalid: {:?}",
+                    index + 1,
+                    validation
+                )
+            }
+            ThreadError::Interrupted { posted, error } => write!(
+                f,
+                "thread interrupted after {} tweets: {}",
+                posted.len(),
+                error
+            ),
+        }
+    }
+}
+
+impl error::Error for ThreadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ThreadError::Invalid { .. } => None,
+            ThreadError::Interrupted { error, .. } => Some(error),
+        }
+    }
+}
+
+impl Twitter {
+    /// Posts a tweet, returning it as created.
+    ///
+    /// Fails with [`Error::DuplicateStatus`]
+    /// when the account recently posted the same text.
+    pub fn update_status(&self, request: &UpdateRequest) -> Result<Tweet> {
+        self.call(&Request::post("statuses/update", http::params(request)))
+    }
+
+    /// Deletes one of the authenticated user's tweets, returning it.
+    ///
+    /// [Docs on Twitter](https://developer.twitter.com/en/docs/tweets/post-and-engage/api-reference/post-statuses-destroy-id)
+    pub fn destroy_status(&self, id: u64) -> Result<Tweet> {
+        self.call(&Request::post(
+            &format!("statuses/destroy/{}", id),
+            Vec::new(),
+        ))
+    }
+
+    /// Posts `items` as a thread, each replying to the one before.
+    ///
+    /// Every item is validated with `config`, which should be the one the
+    /// items were split with, before anything is posted. Returns the tweets
+    /// in thread order.
+    pub fn post_thread(
+        &self,
+        items: &[ThreadItem],
+        config: &Config,
+    ) -> std::result::Result<Vec<Tweet>, ThreadError> {
+        self.chain(None, items, config)
+    }
+
+    /// Continues a thread: as [`Twitter::post_thread`], but the first item
+    /// replies to the tweet `id`.
+    pub fn reply_thread(
+        &self,
+        id: u64,
+        items: &[ThreadItem],
+        config: &Config,
+    ) -> std::result::Result<Vec<Tweet>, ThreadError> {
+        self.chain(Some(id), items, config)
+    }
+
+    fn chain(
+        &self,
+        mut in_reply_to: Option<u64>,
+        items: &[ThreadItem],
+        config: &Config,
+    ) -> std::result::Result<Vec<Tweet>, ThreadError> {
+        for (index, item) in items.iter().enumerate() {
+            match text::validate_tweet(&item.text, config) {
+                Validation::Valid => {}
+                validation => return Err(ThreadError::Invalid { index, validation }),
+            }
+        }
+
+        let mut posted = Vec::with_capacity(items.len());
+        for item in items {
+            let mut request = match in_reply_to {
+                Some(id) => UpdateRequest::reply(item.text.as_str(), id),
+                None => UpdateRequest::new(item.text.as_str()),
+            };
+            if !item.media_ids.is_empty() {
+                request.media_ids = Some(item.media_ids.clone());
+            }
+            match self.update_status(&request) {
+                Ok(tweet) => {
+                    in_reply_to = Some(tweet.id);
+                    posted.push(tweet);
+                }
+                Err(error) => return Err(ThreadError::Interrupted { posted, error }),
+            }
+        }
+        Ok(posted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock::Mock;
+    use crate::http::{Body, Method};
+    use crate::Error;
+    use std::fs;
+    use std::path::Path;
+
+    fn fixture(name: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/tweets")
+            .join(name);
+        fs::read_to_string(path).unwrap()
+    }
+
+    fn tweet(id: u64) -> String {
+        let mut tweet: serde_json::Value =
+            serde_json::from_str(&fixture("plain_text.json")).unwrap();
+        tweet["id"] = id.into();
+        tweet["id_str"] = id.to_string().into();
+        tweet.to_string()
+    }
+
+    fn param<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+        match &request.body {
+            Body::Form(params) => params
+                .iter()
+                .find(|(param, _)| param == name)
+                .map(|(_, value)| value.as_str()),
+            _ => None,
+        }
+    }
+
+    fn form(pairs: &[(&str, &str)]) -> Body {
+        Body::Form(
+            pairs
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn posts_replies() {
+        let mock = Mock::default();
+        mock.respond(200, &fixture("reply.json"));
+        let twitter = Twitter::new(mock.clone());
+
+        let mut request = UpdateRequest::reply("thanks for the heads up!", 1111672826225864704);
+        request.exclude_reply_user_ids = Some(vec![6253282, 2244994945]);
+        let tweet = twitter.update_status(&request).unwrap();
+        assert_eq!(tweet.in_reply_to_status_id, Some(1111672826225864704));
+
+        let mut sent = mock.requests().remove(0);
+        assert_eq!(sent.method, Method::Post);
+        assert_eq!(sent.url, "https://api.twitter.com/1.1/statuses/update.json");
+        if let Body::Form(params) = &mut sent.body {
+            params.sort();
+        }
+        assert_eq!(
+            sent.body,
+            form(&[
+                ("auto_populate_reply_metadata", "true"),
+                ("exclude_reply_user_ids", "6253282,2244994945"),
+                ("in_reply_to_status_id", "1111672826225864704"),
+                ("status", "thanks for the heads up!"),
+            ])
+        );
+    }
+
+    #[test]
+    fn duplicate_status_is_distinct() {
+        let mock = Mock::default();
+        mock.respond(
+            403,
+            r#"{"errors":[{"code":187,"message":"Status is a duplicate."}]}"#,
+        );
+        let twitter = Twitter::new(mock);
+        let error = twitter
+            .update_status(&UpdateRequest::new("hello again"))
+            .unwrap_err();
+        assert!(matches!(error, Error::DuplicateStatus));
+    }
+
+    #[test]
+    fn destroys_tweets() {
+        let mock = Mock::default();
+        mock.respond(200, &fixture("plain_text.json")).respond(
+            404,
+            r#"{"errors":[{"code":144,"message":"No status found with that ID."}]}"#,
+        );
+        let twitter = Twitter::new(mock.clone());
+
+        let tweet = twitter.destroy_status(850006245121695744).unwrap();
+        assert_eq!(tweet.id, 850006245121695744);
+        assert_eq!(
+            mock.requests()[0].url,
+            "https://api.twitter.com/1.1/statuses/destroy/850006245121695744.json"
+        );
+
+        let error = twitter.destroy_status(850006245121695744).unwrap_err();
+        assert_eq!(error.codes(), vec![144]);
+    }
+
+    #[test]
+    fn posts_threads_as_reply_chains() {
+        let mock = Mock::default();
+        mock.respond(200, &tweet(1)).respond(200, &tweet(2));
+        let twitter = Twitter::new(mock.clone());
+
+        let items = vec![
+            ThreadItem {
+                text: "Version 2.0 is out! 🧵".to_string(),
+                media_ids: vec![MediaId(710511363345354753)],
+            },
+            ThreadItem::from("It posts threads."),
+        ];
+        let posted = twitter.post_thread(&items, &Config::default()).unwrap();
+        assert_eq!(
+            posted.iter().map(|tweet| tweet.id).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        let sent = mock.requests();
+        assert_eq!(param(&sent[0], "in_reply_to_status_id"), None);
+        assert_eq!(param(&sent[0], "media_ids"), Some("710511363345354753"));
+        assert_eq!(param(&sent[1], "in_reply_to_status_id"), Some("1"));
+        assert_eq!(param(&sent[1], "media_ids"), None);
+    }
+
+    #[test]
+    fn threads_are_validated_up_front() {
+        let mock = Mock::default();
+        let twitter = Twitter::new(mock.clone());
+        let items = vec![ThreadItem::from("fine"), ThreadItem::from("x".repeat(281))];
+        match twitter.post_thread(&items, &Config::default()) {
+            Err(ThreadError::Invalid {
+                index: 1,
+                validation: Validation::TooLong { .. },
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Fits the default 280 characters, but not the given config.
+        let items = vec![ThreadItem::from("x".repeat(141))];
+        match twitter.reply_thread(42, &items, &Config::v1()) {
+            Err(ThreadError::Invalid {
+                index: 0,
+                validation: Validation::TooLong { .. },
+            }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(mock.requests().is_empty());
+    }
+
+    #[test]
+    fn interrupted_threads_return_posted_tweets() {
+        let mock = Mock::default();
+        mock.respond(200, &tweet(1))
+            .respond(200, &tweet(2))
+            .respond(
+                503,
+                r#"{"errors":[{"code":130,"message":"Over capacity"}]}"#,
+            );
+        let twitter = Twitter::new(mock.clone());
+
+        let items: Vec<ThreadItem> = vec!["one".into(), "two".into(), "three".into()];
+        let (posted, error) = match twitter.reply_thread(42, &items, &Config::default()) {
+            Err(ThreadError::Interrupted { posted, error }) => (posted, error),
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(posted.len(), 2);
+        assert_eq!(error.codes(), vec![130]);
+        assert_eq!(
+            param(&mock.requests()[0], "in_reply_to_status_id"),
+            Some("42")
+        );
+    }
+}
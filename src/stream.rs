@@ -0,0 +1,1178 @@
+//! Realtime tweets from the v1.1 streaming API.
+//!
+//! A [`Stream`] is a blocking iterator over the messages of one
+//! connection, ending when Twitter closes it. A [`ReconnectingStream`]
+//! reconnects as Twitter asks clients to, and goes on until an error that
+//! reconnecting cannot fix. [`Resumed`] picks up where a consumer left
+//! off, searching for the tweets it missed while streaming new ones.
+
+use crate::api::Tweet;
+use crate::http::{self, Body, Method, Request, STREAM_URL};
+use crate::search::SearchRequest;
+use crate::{Error, Result, Twitter};
+use serde_json::Value;
+use std::cmp;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// How long a stream may go without data, not even a keep-alive, before it
+/// is considered stalled.
+///
+/// Twitter sends keep-alives every 30 seconds, so this allows for three
+/// missed ones.
+pub const STALL_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// The longest message a length delimited stream may announce, in bytes.
+///
+/// Tweets are a few kilobytes, so anything near this is a broken stream.
+pub const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+/// How many times [`Twitter::resume_filter_stream`] tries to connect before
+/// giving up.
+pub const MAX_RESUME_ATTEMPTS: u32 = 5;
+
+/// Statuses/filter request
+///
+/// At least one of `track`, `follow` and `locations` is required; tweets
+/// matching any of them are delivered.
+///
+/// # Resource Information
+///
+/// Response formats: **JSON**
+///
+/// Requires authentication?: **Yes (user context only)**
+///
+/// Rate limited?: **Yes**, one standing connection per account
+///
+/// [Docs on Twitter](https://developer.twitter.com/en/docs/tweets/filter-realtime/api-reference/post-statuses-filter)
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct FilterRequest {
+    /// Up to 400 phrases. Words in a phrase must all appear in a tweet, in
+    /// any order.
+    pub track: Option<Vec<String>>,
+
+    /// Up to 5000 user IDs whose tweets, and replies and retweets of them,
+    /// to deliver.
+    pub follow: Option<Vec<u64>>,
+
+    /// Up to 25 bounding boxes, each as south-west longitude and latitude
+    /// then north-east longitude and latitude.
+    pub locations: Option<Vec<f64>>,
+
+    /// Only tweets in these languages, as BCP 47 codes.
+    pub language: Option<Vec<String>>,
+
+    /// Prefix every message with its length.
+    pub delimited: Option<Delimited>,
+
+    /// Send [`Warning`]s when the client falls behind.
+    pub stall_warnings: Option<bool>,
+}
+
+/// Statuses/sample request
+///
+/// # Resource Information
+///
+/// Response formats: **JSON**
+///
+/// Requires authentication?: **Yes (user context only)**
+///
+/// Rate limited?: **Yes**, one standing connection per account
+///
+/// [Docs on Twitter](https://developer.twitter.com/en/docs/tweets/sample-realtime/api-reference/get-statuses-sample)
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct SampleRequest {
+    /// Only tweets in these languages, as BCP 47 codes.
+    pub language: Option<Vec<String>>,
+
+    /// Prefix every message with its length.
+    pub delimited: Option<Delimited>,
+
+    /// Send [`Warning`]s when the client falls behind.
+    pub stall_warnings: Option<bool>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Delimited {
+    Length,
+}
+
+/// A message from a stream.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StreamMessage {
+    Tweet(Box<Tweet>),
+    /// A tweet was deleted and must be removed from storage.
+    Delete(StatusDeletion),
+    /// Location data must be removed from a user's tweets.
+    ScrubGeo(ScrubGeo),
+    /// Matching tweets were left out to stay within the stream's share of
+    /// all tweets.
+    Limit(Limit),
+    StatusWithheld(StatusWithheld),
+    UserWithheld(UserWithheld),
+    /// Twitter is about to close the connection.
+    Disconnect(Disconnect),
+    /// The client is falling behind, with `stall_warnings`.
+    Warning(Warning),
+    /// A message of a kind not modelled here.
+    Unknown(Value),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct StatusDeletion {
+    pub id: u64,
+    pub user_id: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ScrubGeo {
+    pub user_id: u64,
+    /// Location is to be removed from this tweet and all earlier ones.
+    pub up_to_status_id: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Limit {
+    /// Tweets left out since the connection was opened.
+    pub track: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct StatusWithheld {
+    pub id: u64,
+    pub user_id: u64,
+    /// Two-letter country codes, or `XY` for a DMCA takedown.
+    pub withheld_in_countries: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct UserWithheld {
+    pub id: u64,
+    pub withheld_in_countries: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Disconnect {
+    /// Why, like 7 when another connection was opened for the account.
+    pub code: u32,
+    pub stream_name: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Warning {
+    /// Like `FALLING_BEHIND`.
+    pub code: String,
+    pub message: String,
+    /// How full the queue of messages for the client is.
+    pub percent_full: Option<u32>,
+}
+
+impl StreamMessage {
+    /// Parses one message of a stream.
+    pub fn parse(json: &[u8]) -> Result<StreamMessage> {
+        let value: Value = serde_json::from_slice(json)?;
+        let message = if let Some(delete) = value.get("delete") {
+            StreamMessage::Delete(serde_json::from_value(delete["status"].clone())?)
+        } else if let Some(scrub) = value.get("scrub_geo") {
+            StreamMessage::ScrubGeo(serde_json::from_value(scrub.clone())?)
+        } else if let Some(limit) = value.get("limit") {
+            StreamMessage::Limit(serde_json::from_value(limit.clone())?)
+        } else if let Some(withheld) = value.get("status_withheld") {
+            StreamMessage::StatusWithheld(serde_json::from_value(withheld.clone())?)
+        } else if let Some(withheld) = value.get("user_withheld") {
+            StreamMessage::UserWithheld(serde_json::from_value(withheld.clone())?)
+        } else if let Some(disconnect) = value.get("disconnect") {
+            StreamMessage::Disconnect(serde_json::from_value(disconnect.clone())?)
+        } else if let Some(warning) = value.get("warning") {
+            StreamMessage::Warning(serde_json::from_value(warning.clone())?)
+        } else if value.get("id").is_some() && value.get("user").is_some() {
+            StreamMessage::Tweet(Box::new(serde_json::from_value(value)?))
+        } else {
+            StreamMessage::Unknown(value)
+        };
+        Ok(message)
+    }
+}
+
+/// The messages of one stream connection.
+///
+/// Blank keep-alive lines are skipped, and both newline and length
+/// delimited streams are understood. A message cut short by the connection
+/// closing is an [`io::ErrorKind::UnexpectedEof`] error, and a connection
+/// without data for [`STALL_TIMEOUT`] an [`io::ErrorKind::TimedOut`] one.
+/// A length over [`MAX_MESSAGE_SIZE`] is an [`io::ErrorKind::InvalidData`]
+/// error.
+pub struct Stream {
+    body: BufReader<Watched>,
+}
+
+impl Stream {
+    fn new(body: Box<dyn Read + Send>, stall_timeout: Duration) -> Stream {
+        Stream {
+            body: BufReader::new(Watched::new(body, stall_timeout)),
+        }
+    }
+
+    /// The JSON of the next message, shared with the v2 streams.
+    pub(crate) fn next_json(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if self.body.read_until(b'\n', &mut line)? == 0 {
+                return Ok(None);
+            }
+            if !line.ends_with(b"\n") {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            let json = line.trim_ascii();
+            if json.is_empty() {
+                continue;
+            }
+            if json.iter().all(u8::is_ascii_digit) {
+                let len = std::str::from_utf8(json)
+                    .unwrap()
+                    .parse()
+                    .ok()
+                    .filter(|&len| len <= MAX_MESSAGE_SIZE)
+                    .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))?;
+                let mut message = vec![0; len];
+                self.body.read_exact(&mut message)?;
+                return Ok(Some(message));
+            }
+            return Ok(Some(json.to_vec()));
+        }
+    }
+
+    fn next_message(&mut self) -> Result<Option<StreamMessage>> {
+        match self.next_json()? {
+            Some(json) => StreamMessage::parse(&json).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Iterator for Stream {
+    type Item = Result<StreamMessage>;
+
+    fn next(&mut self) -> Option<Result<StreamMessage>> {
+        self.next_message().transpose()
+    }
+}
+
+impl fmt::Debug for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Stream").finish()
+    }
+}
+
+/// A body read on its own thread, so reads can time out whatever the
+/// transport does.
+///
+/// A stalled read keeps its thread blocked until the transport gives up or
+/// the connection closes; the data it then gets is dropped.
+struct Watched {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    chunk: io::Cursor<Vec<u8>>,
+    stall_timeout: Duration,
+    done: bool,
+}
+
+impl Watched {
+    fn new(mut body: Box<dyn Read + Send>, stall_timeout: Duration) -> Watched {
+        let (sender, chunks) = mpsc::sync_channel(1);
+        thread::spawn(move || loop {
+            let mut chunk = vec![0; 8 * 1024];
+            let read = match body.read(&mut chunk) {
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                read => read,
+            };
+            let last = !matches!(read, Ok(len) if len > 0);
+            let read = read.map(|len| {
+                chunk.truncate(len);
+                chunk
+            });
+            if sender.send(read).is_err() || last {
+                return;
+            }
+        });
+        Watched {
+            chunks,
+            chunk: io::Cursor::new(Vec::new()),
+            stall_timeout,
+            done: false,
+        }
+    }
+}
+
+impl Read for Watched {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.chunk.read(buf)?;
+            if read > 0 || buf.is_empty() || self.done {
+                return Ok(read);
+            }
+            match self.chunks.recv_timeout(self.stall_timeout) {
+                Ok(Ok(chunk)) => {
+                    self.done = chunk.is_empty();
+                    self.chunk = io::Cursor::new(chunk);
+                }
+                Ok(Err(error)) => {
+                    self.done = true;
+                    return Err(error);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "no data on the stream for too long",
+                    ))
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.done = true;
+                    return Ok(0);
+                }
+            }
+        }
+    }
+}
+
+/// Waits between reconnection attempts, as Twitter documents for streams.
+///
+/// Each kind of failure backs off on its own, and all start over once a
+/// connection delivers a message.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Backoff {
+    network: u32,
+    http: u32,
+    rate_limited: u32,
+}
+
+impl Backoff {
+    /// After a network error or stall: 250 ms more each attempt, up to 16 s.
+    pub fn network(&mut self) -> Duration {
+        self.network += 1;
+        cmp::min(
+            Duration::from_millis(250) * self.network,
+            Duration::from_secs(16),
+        )
+    }
+
+    /// After an HTTP error: 5 s, doubling each attempt up to 320 s.
+    pub fn http(&mut self) -> Duration {
+        self.http += 1;
+        cmp::min(
+            doubled(Duration::from_secs(5), self.http),
+            Duration::from_secs(320),
+        )
+    }
+
+    /// After a 420 or 429 status: 1 minute, doubling each attempt.
+    pub fn rate_limited(&mut self) -> Duration {
+        self.rate_limited += 1;
+        doubled(Duration::from_secs(60), self.rate_limited)
+    }
+
+    pub fn reset(&mut self) {
+        *self = Backoff::default();
+    }
+}
+
+/// `base` doubled for each attempt after the first.
+fn doubled(base: Duration, attempt: u32) -> Duration {
+    base.saturating_mul(2u32.saturating_pow(attempt - 1))
+}
+
+/// What a [`ReconnectingStream`] reports.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StreamEvent {
+    Message(StreamMessage),
+    /// A connection was opened.
+    Connected,
+    /// The connection was lost or refused, and is retried after `wait`.
+    Reconnecting {
+        reason: Reconnect,
+        wait: Duration,
+    },
+}
+
+/// Why a [`ReconnectingStream`] reconnects.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Reconnect {
+    /// The connection could not be opened or broke.
+    Network(io::ErrorKind),
+    /// Nothing, not even a keep-alive, arrived for [`STALL_TIMEOUT`].
+    Stall,
+    /// Twitter closed the connection.
+    Closed,
+    /// Twitter failed with a server error.
+    Http(u16),
+    /// Too many connections were attempted, with a 420 or 429 status.
+    RateLimited(u16),
+}
+
+impl Reconnect {
+    /// The error to give up with instead of reconnecting.
+    fn into_error(self) -> Error {
+        match self {
+            Reconnect::Network(kind) => io::Error::from(kind).into(),
+            Reconnect::Stall => io::Error::from(io::ErrorKind::TimedOut).into(),
+            Reconnect::Closed => io::Error::from(io::ErrorKind::UnexpectedEof).into(),
+            Reconnect::Http(status) | Reconnect::RateLimited(status) => Error::Api {
+                status,
+                errors: Vec::new(),
+            },
+        }
+    }
+}
+
+/// A stream that reconnects with [`Backoff`] when the connection fails.
+///
+/// Sleeps with [`Transport::sleep`](crate::http::Transport::sleep) before
+/// reconnecting. Errors reconnecting cannot fix, like a 401 for bad
+/// credentials, end the iteration; an unparseable message is returned as
+/// an error without ending it.
+pub struct ReconnectingStream<'a> {
+    twitter: &'a Twitter,
+    request: Request,
+    stall_timeout: Duration,
+    stream: Option<Stream>,
+    backoff: Backoff,
+    wait: Option<Duration>,
+    done: bool,
+}
+
+impl<'a> ReconnectingStream<'a> {
+    fn reconnect(&mut self, reason: Reconnect) -> StreamEvent {
+        self.stream = None;
+        let wait = match reason {
+            Reconnect::Network(_) | Reconnect::Stall | Reconnect::Closed => self.backoff.network(),
+            Reconnect::Http(_) => self.backoff.http(),
+            Reconnect::RateLimited(_) => self.backoff.rate_limited(),
+        };
+        self.wait = Some(wait);
+        StreamEvent::Reconnecting { reason, wait }
+    }
+}
+
+impl<'a> Iterator for ReconnectingStream<'a> {
+    type Item = Result<StreamEvent>;
+
+    fn next(&mut self) -> Option<Result<StreamEvent>> {
+        if self.done {
+            return None;
+        }
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => {
+                if let Some(wait) = self.wait.take() {
+                    self.twitter.transport.sleep(wait);
+                }
+                let event = match self.twitter.open_with(&self.request, self.stall_timeout) {
+                    Ok(stream) => {
+                        self.stream = Some(stream);
+                        StreamEvent::Connected
+                    }
+                    Err(Error::Io(error)) => self.reconnect(Reconnect::Network(error.kind())),
+                    Err(Error::Api { status, .. }) if status == 420 || status == 429 => {
+                        self.reconnect(Reconnect::RateLimited(status))
+                    }
+                    Err(Error::Api { status, .. }) if status >= 500 => {
+                        self.reconnect(Reconnect::Http(status))
+                    }
+                    Err(error) => {
+                        self.done = true;
+                        return Some(Err(error));
+                    }
+                };
+                return Some(Ok(event));
+            }
+        };
+        let event = match stream.next() {
+            Some(Ok(message)) => {
+                self.backoff.reset();
+                StreamEvent::Message(message)
+            }
+            Some(Err(Error::Io(error))) => match error.kind() {
+                io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+                    self.reconnect(Reconnect::Stall)
+                }
+                kind => self.reconnect(Reconnect::Network(kind)),
+            },
+            Some(Err(error)) => return Some(Err(error)),
+            None => self.reconnect(Reconnect::Closed),
+        };
+        Some(Ok(event))
+    }
+}
+
+impl<'a> fmt::Debug for ReconnectingStream<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReconnectingStream")
+            .field("request", &self.request)
+            .field("backoff", &self.backoff)
+            .finish()
+    }
+}
+
+/// Tweets missed since a restart followed by live messages, see
+/// [`Twitter::resume_filter_stream`].
+///
+/// Tweets found by the search and those streamed while it ran come first,
+/// ordered by ID, then the other messages streamed meanwhile, then the rest
+/// of the stream as it arrives. Tweets found both ways are returned once.
+///
+/// The stream reconnects as a [`ReconnectingStream`] does, and passes on
+/// its [`StreamEvent::Reconnecting`] and [`StreamEvent::Connected`] events.
+/// Tweets posted while it was disconnected are not searched for; resume
+/// again from the last tweet seen to fill the gap. It ends with the error
+/// that stopped it.
+pub struct Resumed {
+    backfill: std::vec::IntoIter<Tweet>,
+    /// What else was streamed during the search, after the backfill.
+    queued: std::vec::IntoIter<Result<StreamEvent>>,
+    seen: HashSet<u64>,
+    live: Receiver<Result<StreamEvent>>,
+}
+
+impl Iterator for Resumed {
+    type Item = Result<StreamEvent>;
+
+    fn next(&mut self) -> Option<Result<StreamEvent>> {
+        if let Some(tweet) = self.backfill.next() {
+            return Some(Ok(StreamEvent::Message(StreamMessage::Tweet(Box::new(
+                tweet,
+            )))));
+        }
+        if let Some(event) = self.queued.next() {
+            return Some(event);
+        }
+        loop {
+            match self.live.recv().ok()? {
+                Ok(StreamEvent::Message(StreamMessage::Tweet(tweet)))
+                    if self.seen.contains(&tweet.id) => {}
+                event => return Some(event),
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Resumed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Resumed")
+            .field("backfill", &self.backfill.len())
+            .field("queued", &self.queued.len())
+            .finish()
+    }
+}
+
+impl FilterRequest {
+    fn request(&self) -> Request {
+        Request {
+            method: Method::Post,
+            url: format!("{}/statuses/filter.json", STREAM_URL),
+            query: Vec::new(),
+            body: Body::Form(http::params(self)),
+        }
+    }
+}
+
+impl SampleRequest {
+    fn request(&self) -> Request {
+        Request {
+            method: Method::Get,
+            url: format!("{}/statuses/sample.json", STREAM_URL),
+            query: http::params(self),
+            body: Body::Empty,
+        }
+    }
+}
+
+impl Twitter {
+    /// Streams tweets matching `request` as they are posted.
+    pub fn filter_stream(&self, request: &FilterRequest) -> Result<Stream> {
+        self.open(&request.request())
+    }
+
+    /// Streams a small random sample of all tweets.
+    pub fn sample_stream(&self, request: &SampleRequest) -> Result<Stream> {
+        self.open(&request.request())
+    }
+
+    /// Streams tweets matching `request` along with those posted after
+    /// `last_seen`, searched for with `search`.
+    ///
+    /// The stream is read with [`Twitter::reconnecting_filter_stream`] on
+    /// its own thread, and the search starts once it is connected, so no
+    /// tweet falls between the two. `search` should match the same tweets
+    /// as `request`. If the search fails, or the stream cannot connect in
+    /// [`MAX_RESUME_ATTEMPTS`] tries, the error is returned and the stream is
+    /// dropped, its thread ending as soon as it next hears from Twitter.
+    pub fn resume_filter_stream(
+        &self,
+        request: &FilterRequest,
+        search: &SearchRequest,
+        last_seen: u64,
+    ) -> Result<Resumed> {
+        let twitter = Twitter {
+            transport: Arc::clone(&self.transport),
+        };
+        let request = request.clone();
+        let (sender, live) = mpsc::channel();
+        thread::spawn(move || {
+            for event in twitter.reconnecting_filter_stream(&request) {
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut attempts = 0;
+        loop {
+            match live.recv() {
+                Ok(Ok(StreamEvent::Connected)) | Err(_) => break,
+                Ok(Ok(StreamEvent::Reconnecting { reason, .. })) => {
+                    attempts += 1;
+                    if attempts == MAX_RESUME_ATTEMPTS {
+                        return Err(reason.into_error());
+                    }
+                }
+                Ok(Ok(StreamEvent::Message(_))) => {}
+                Ok(Err(error)) => return Err(error),
+            }
+        }
+
+        let mut backfill = self.backfill(search, last_seen)?;
+        let mut queued = Vec::new();
+        while let Ok(event) = live.try_recv() {
+            match event {
+                Ok(StreamEvent::Message(StreamMessage::Tweet(tweet))) => backfill.push(*tweet),
+                event => queued.push(event),
+            }
+        }
+        backfill.sort_by_key(|tweet| tweet.id);
+        backfill.dedup_by_key(|tweet| tweet.id);
+        Ok(Resumed {
+            seen: backfill.iter().map(|tweet| tweet.id).collect(),
+            backfill: backfill.into_iter(),
+            queued: queued.into_iter(),
+            live,
+        })
+    }
+
+    /// Every tweet `search` finds after `last_seen`, paging with `max_id`.
+    fn backfill(&self, search: &SearchRequest, last_seen: u64) -> Result<Vec<Tweet>> {
+        let mut request = SearchRequest {
+            since_id: Some(last_seen),
+            max_id: None,
+            ..search.clone()
+        };
+        let mut tweets = Vec::new();
+        loop {
+            let page = self.search(&request)?.statuses;
+            let oldest = page.iter().map(|tweet| tweet.id).min();
+            tweets.extend(page.into_iter().filter(|tweet| tweet.id > last_seen));
+            match oldest {
+                Some(oldest) if oldest > last_seen + 1 => request.max_id = Some(oldest - 1),
+                _ => return Ok(tweets),
+            }
+        }
+    }
+
+    /// As [`Twitter::filter_stream`], reconnecting when the connection
+    /// fails.
+    pub fn reconnecting_filter_stream(&self, request: &FilterRequest) -> ReconnectingStream<'_> {
+        self.reconnecting(request.request())
+    }
+
+    /// As [`Twitter::sample_stream`], reconnecting when the connection
+    /// fails.
+    pub fn reconnecting_sample_stream(&self, request: &SampleRequest) -> ReconnectingStream<'_> {
+        self.reconnecting(request.request())
+    }
+
+    fn reconnecting(&self, request: Request) -> ReconnectingStream<'_> {
+        ReconnectingStream {
+            twitter: self,
+            request,
+            stall_timeout: STALL_TIMEOUT,
+            stream: None,
+            backoff: Backoff::default(),
+            wait: None,
+            done: false,
+        }
+    }
+
+    pub(crate) fn open(&self, request: &Request) -> Result<Stream> {
+        self.open_with(request, STALL_TIMEOUT)
+    }
+
+    fn open_with(&self, request: &Request, stall_timeout: Duration) -> Result<Stream> {
+        let mut response = self.transport.open(request)?;
+        if response.status >= 400 {
+            let mut body = Vec::new();
+            response.body.read_to_end(&mut body)?;
+            return Err(Error::from_response(response.status, &body));
+        }
+        Ok(Stream::new(response.body, stall_timeout))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock::Mock;
+    use crate::http::Streaming;
+
+    fn tweet() -> String {
+        let json = include_str!("../tests/fixtures/tweets/plain_text.json");
+        serde_json::from_str::<Value>(json).unwrap().to_string()
+    }
+
+    #[test]
+    fn parses_messages() {
+        let body = [
+            tweet().as_str(),
+            "",
+            r#"{"delete":{"status":{"id":1234,"id_str":"1234","user_id":3,"user_id_str":"3"},"timestamp_ms":"1553000000000"}}"#,
+            r#"{"scrub_geo":{"user_id":14090548,"user_id_str":"14090548","up_to_status_id":23260136625,"up_to_status_id_str":"23260136625"}}"#,
+            r#"{"limit":{"track":1234,"timestamp_ms":"1553000000000"}}"#,
+            r#"{"status_withheld":{"id":1234567890,"user_id":123456,"withheld_in_countries":["DE","AR"],"timestamp_ms":"1553000000000"}}"#,
+            r#"{"user_withheld":{"id":123456,"withheld_in_countries":["DE","AR"]}}"#,
+            r#"{"warning":{"code":"FALLING_BEHIND","message":"Your connection is falling behind.","percent_full":60}}"#,
+            r#"{"friends":[1,2]}"#,
+            r#"{"disconnect":{"code":4,"stream_name":"example-statuses","reason":"duplicate stream"}}"#,
+            "",
+        ]
+        .join("\r\n");
+        let mock = Mock::default();
+        mock.stream(200, &body);
+        let twitter = Twitter::new(mock.clone());
+
+        let request = FilterRequest {
+            track: Some(vec!["twitter api".to_string(), "#rust".to_string()]),
+            follow: Some(vec![2244994945]),
+            locations: Some(vec![-122.75, 36.8, -121.75, 37.8]),
+            ..FilterRequest::default()
+        };
+        let messages = twitter
+            .filter_stream(&request)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(messages.len(), 9);
+        assert!(
+            matches!(&messages[0], StreamMessage::Tweet(tweet) if tweet.id == 850006245121695744)
+        );
+        assert_eq!(
+            messages[1],
+            StreamMessage::Delete(StatusDeletion {
+                id: 1234,
+                user_id: 3
+            })
+        );
+        assert!(
+            matches!(&messages[2], StreamMessage::ScrubGeo(scrub) if scrub.up_to_status_id == 23260136625)
+        );
+        assert_eq!(messages[3], StreamMessage::Limit(Limit { track: 1234 }));
+        assert!(
+            matches!(&messages[4], StreamMessage::StatusWithheld(withheld) if withheld.withheld_in_countries == ["DE", "AR"])
+        );
+        assert!(
+            matches!(&messages[5], StreamMessage::UserWithheld(withheld) if withheld.id == 123456)
+        );
+        assert!(
+            matches!(&messages[6], StreamMessage::Warning(warning) if warning.percent_full == Some(60))
+        );
+        assert!(matches!(&messages[7], StreamMessage::Unknown(_)));
+        assert!(
+            matches!(&messages[8], StreamMessage::Disconnect(disconnect) if disconnect.code == 4)
+        );
+
+        let sent = &mock.requests()[0];
+        assert_eq!(
+            sent.url,
+            "https://stream.twitter.com/1.1/statuses/filter.json"
+        );
+        let mut form = match &sent.body {
+            Body::Form(form) => form.clone(),
+            body => panic!("unexpected body {:?}", body),
+        };
+        form.sort();
+        assert_eq!(
+            form,
+            vec![
+                ("follow".to_string(), "2244994945".to_string()),
+                (
+                    "locations".to_string(),
+                    "-122.75,36.8,-121.75,37.8".to_string()
+                ),
+                ("track".to_string(), "twitter api,#rust".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_length_delimited_streams() {
+        let delete = "{\"delete\":{\"status\":{\"id\":1,\"user_id\":2}}}\r\n";
+        let tweet = format!("{}\r\n", tweet());
+        let body = format!(
+            "\r\n{}\r\n{}{}\r\n{}",
+            delete.len(),
+            delete,
+            tweet.len(),
+            tweet
+        );
+        let mock = Mock::default();
+        mock.stream(200, &body);
+        let twitter = Twitter::new(mock.clone());
+
+        let request = SampleRequest {
+            delimited: Some(Delimited::Length),
+            stall_warnings: Some(true),
+            ..SampleRequest::default()
+        };
+        let messages = twitter
+            .sample_stream(&request)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert!(matches!(messages[0], StreamMessage::Delete(_)));
+        assert!(matches!(messages[1], StreamMessage::Tweet(_)));
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            mock.requests()[0].query[0],
+            ("delimited".to_string(), "length".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_oversized_lengths() {
+        for len in [MAX_MESSAGE_SIZE + 1, usize::MAX] {
+            let mock = Mock::default();
+            mock.stream(200, &format!("{}\r\n{{}}", len));
+            let twitter = Twitter::new(mock);
+
+            let mut stream = twitter.sample_stream(&SampleRequest::default()).unwrap();
+            match stream.next() {
+                Some(Err(Error::Io(error))) => {
+                    assert_eq!(error.kind(), io::ErrorKind::InvalidData)
+                }
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn stream_errors() {
+        let mock = Mock::default();
+        mock.stream(420, "Exceeded connection limit for user")
+            .stream(200, "{\"limit\":{\"track\":1}}\r\n{\"limit\"");
+        let twitter = Twitter::new(mock);
+
+        match twitter.sample_stream(&SampleRequest::default()) {
+            Err(Error::Api { status: 420, .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let mut stream = twitter.sample_stream(&SampleRequest::default()).unwrap();
+        assert!(matches!(stream.next(), Some(Ok(StreamMessage::Limit(_)))));
+        match stream.next() {
+            Some(Err(Error::Io(error))) => assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn backs_off_as_documented() {
+        let mut backoff = Backoff::default();
+        let network: Vec<_> = (0..66).map(|_| backoff.network().as_millis()).collect();
+        assert_eq!(network[..3], [250, 500, 750]);
+        assert_eq!(network[63..], [16000, 16000, 16000]);
+        let http: Vec<_> = (0..8).map(|_| backoff.http().as_secs()).collect();
+        assert_eq!(http, [5, 10, 20, 40, 80, 160, 320, 320]);
+        let rate_limited: Vec<_> = (0..3).map(|_| backoff.rate_limited().as_secs()).collect();
+        assert_eq!(rate_limited, [60, 120, 240]);
+        backoff.reset();
+        assert_eq!(backoff.http(), Duration::from_secs(5));
+    }
+
+    /// A body that times out once its data is read, like a stalled stream.
+    struct Stalling(io::Cursor<Vec<u8>>);
+
+    impl Read for Stalling {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.read(buf)? {
+                0 => Err(io::ErrorKind::TimedOut.into()),
+                read => Ok(read),
+            }
+        }
+    }
+
+    #[test]
+    fn reconnects_with_backoff() {
+        let mock = Mock::default();
+        mock.stream_with(Err(io::ErrorKind::ConnectionRefused.into()))
+            .stream(503, "")
+            .stream(503, "")
+            .stream(420, "Enhance Your Calm")
+            .stream_with(Ok(Streaming {
+                status: 200,
+                headers: Vec::new(),
+                body: Box::new(Stalling(io::Cursor::new(
+                    b"{\"limit\":{\"track\":5}}\r\n".to_vec(),
+                ))),
+            }))
+            .stream(200, "\r\n")
+            .stream(
+                401,
+                r#"{"errors":[{"code":32,"message":"Could not authenticate you."}]}"#,
+            );
+        let twitter = Twitter::new(mock.clone());
+
+        let request = FilterRequest {
+            track: Some(vec!["rust".to_string()]),
+            ..FilterRequest::default()
+        };
+        let mut events = twitter.reconnecting_filter_stream(&request);
+        let reconnecting = |reason, millis| StreamEvent::Reconnecting {
+            reason,
+            wait: Duration::from_millis(millis),
+        };
+        let expected = vec![
+            reconnecting(Reconnect::Network(io::ErrorKind::ConnectionRefused), 250),
+            reconnecting(Reconnect::Http(503), 5000),
+            reconnecting(Reconnect::Http(503), 10000),
+            reconnecting(Reconnect::RateLimited(420), 60000),
+            StreamEvent::Connected,
+            StreamEvent::Message(StreamMessage::Limit(Limit { track: 5 })),
+            reconnecting(Reconnect::Stall, 250),
+            StreamEvent::Connected,
+            reconnecting(Reconnect::Closed, 500),
+        ];
+        for event in expected {
+            assert_eq!(events.next().unwrap().unwrap(), event);
+        }
+        match events.next() {
+            Some(Err(error)) => assert_eq!(error.codes(), vec![32]),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(events.next().is_none());
+
+        let sleeps: Vec<_> = mock.sleeps().iter().map(Duration::as_millis).collect();
+        assert_eq!(sleeps, [250, 5000, 10000, 60000, 250, 500]);
+        assert_eq!(mock.requests().len(), 7);
+    }
+
+    /// A body that blocks once its data is read, until `release` is dropped.
+    struct Blocking {
+        data: io::Cursor<Vec<u8>>,
+        release: Receiver<()>,
+    }
+
+    impl Read for Blocking {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.data.read(buf)? {
+                0 => {
+                    let _ = self.release.recv();
+                    Ok(0)
+                }
+                read => Ok(read),
+            }
+        }
+    }
+
+    #[test]
+    fn notices_stalls_the_transport_misses() {
+        let (release, blocked) = mpsc::channel();
+        let mock = Mock::default();
+        mock.stream_with(Ok(Streaming {
+            status: 200,
+            headers: Vec::new(),
+            body: Box::new(Blocking {
+                data: io::Cursor::new(b"{\"limit\":{\"track\":5}}\r\n".to_vec()),
+                release: blocked,
+            }),
+        }))
+        .stream(200, "");
+        let twitter = Twitter::new(mock);
+
+        let mut events = twitter.reconnecting_sample_stream(&SampleRequest::default());
+        events.stall_timeout = Duration::from_millis(50);
+        let expected = vec![
+            StreamEvent::Connected,
+            StreamEvent::Message(StreamMessage::Limit(Limit { track: 5 })),
+            StreamEvent::Reconnecting {
+                reason: Reconnect::Stall,
+                wait: Duration::from_millis(250),
+            },
+            StreamEvent::Connected,
+        ];
+        for event in expected {
+            assert_eq!(events.next().unwrap().unwrap(), event);
+        }
+        drop(release);
+    }
+
+    #[test]
+    fn resumes_with_a_backfill() {
+        let tweet = |id: u64| {
+            let mut tweet: Value =
+                serde_json::from_str(include_str!("../tests/fixtures/tweets/plain_text.json"))
+                    .unwrap();
+            tweet["id"] = id.into();
+            tweet
+        };
+        let page = |ids: &[u64]| {
+            let statuses: Vec<_> = ids.iter().map(|&id| tweet(id)).collect();
+            serde_json::json!({
+                "statuses": statuses,
+                "search_metadata": {
+                    "completed_in": 0.01, "count": 3, "max_id": 0, "max_id_str": "0",
+                    "query": "rust", "since_id": 3, "since_id_str": "3",
+                },
+            })
+            .to_string()
+        };
+        let streamed = [
+            tweet(5).to_string(),
+            r#"{"limit":{"track":2}}"#.to_string(),
+            tweet(9).to_string(),
+            tweet(7).to_string(),
+            tweet(10).to_string(),
+            String::new(),
+        ];
+        let mock = Mock::default();
+        mock.stream(200, &streamed.join("\r\n"))
+            .stream(
+                401,
+                r#"{"errors":[{"code":32,"message":"Could not authenticate you."}]}"#,
+            )
+            .respond(200, &page(&[8, 7, 6]))
+            .respond(200, &page(&[5, 4]));
+        let twitter = Twitter::new(mock.clone());
+
+        let request = FilterRequest {
+            track: Some(vec!["rust".to_string()]),
+            ..FilterRequest::default()
+        };
+        let search = SearchRequest {
+            count: Some(3),
+            ..SearchRequest::new("rust")
+        };
+        let mut messages: Vec<_> = twitter
+            .resume_filter_stream(&request, &search, 3)
+            .unwrap()
+            .collect();
+        match messages.pop() {
+            Some(Err(error)) => assert_eq!(error.codes(), vec![32]),
+            other => panic!("unexpected {:?}", other),
+        }
+        let mut ids = Vec::new();
+        let mut limits = 0;
+        let mut reconnects = 0;
+        for event in messages {
+            match event.unwrap() {
+                StreamEvent::Message(StreamMessage::Tweet(tweet)) => ids.push(tweet.id),
+                StreamEvent::Message(StreamMessage::Limit(Limit { track: 2 })) => limits += 1,
+                StreamEvent::Reconnecting {
+                    reason: Reconnect::Closed,
+                    ..
+                } => reconnects += 1,
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        // Whether 10, the limit notice and the reconnection came during the
+        // search or after it varies, but tweets always come in order.
+        assert_eq!(ids, [4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(limits, 1);
+        assert_eq!(reconnects, 1);
+
+        let sent = mock.requests();
+        assert_eq!(
+            sent[0].url,
+            "https://stream.twitter.com/1.1/statuses/filter.json"
+        );
+        let sent: Vec<_> = sent
+            .into_iter()
+            .filter(|request| request.url.starts_with(http::API_URL))
+            .collect();
+        let query = |request: &Request, name| {
+            request
+                .query
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+        assert_eq!(query(&sent[0], "since_id"), Some("3".to_string()));
+        assert_eq!(query(&sent[0], "max_id"), None);
+        assert_eq!(query(&sent[1], "max_id"), Some("5".to_string()));
+        assert_eq!(sent.len(), 2);
+    }
+
+    #[test]
+    fn stops_streaming_when_the_backfill_fails() {
+        let (release, blocked) = mpsc::channel();
+        let mock = Mock::default();
+        mock.stream_with(Ok(Streaming {
+            status: 200,
+            headers: Vec::new(),
+            body: Box::new(Blocking {
+                data: io::Cursor::new(b"\r\n".to_vec()),
+                release: blocked,
+            }),
+        }))
+        .respond(
+            401,
+            r#"{"errors":[{"code":32,"message":"Could not authenticate you."}]}"#,
+        );
+        let twitter = Twitter::new(mock.clone());
+
+        let request = FilterRequest {
+            track: Some(vec!["rust".to_string()]),
+            ..FilterRequest::default()
+        };
+        match twitter.resume_filter_stream(&request, &SearchRequest::new("rust"), 3) {
+            Err(error) => assert_eq!(error.codes(), vec![32]),
+            Ok(resumed) => panic!("unexpected {:?}", resumed),
+        }
+
+        // Once the connection closes, the reader stops instead of
+        // reconnecting.
+        drop(release);
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[test]
+    fn gives_up_resuming_when_the_stream_cannot_connect() {
+        let mock = Mock::default();
+        for _ in 0..MAX_RESUME_ATTEMPTS {
+            mock.stream(503, "Service Unavailable");
+        }
+        // Ends the reader, which can run ahead of the attempts counted.
+        mock.stream(
+            401,
+            r#"{"errors":[{"code":32,"message":"Could not authenticate you."}]}"#,
+        );
+        let twitter = Twitter::new(mock.clone());
+
+        let request = FilterRequest {
+            track: Some(vec!["rust".to_string()]),
+            ..FilterRequest::default()
+        };
+        match twitter.resume_filter_stream(&request, &SearchRequest::new("rust"), 3) {
+            Err(Error::Api { status: 503, .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        // Nothing is searched for.
+        assert!(mock
+            .requests()
+            .iter()
+            .all(|request| request.url.starts_with(STREAM_URL)));
+    }
+}
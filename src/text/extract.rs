@@ -0,0 +1,707 @@
+use super::tld;
+use crate::api::{Hashtag, Symbol, TweetEntities, Url, UserMention};
+use fancy_regex::{Captures, Regex};
+use std::ops::Range;
+use std::sync::OnceLock;
+
+/// URLs longer than this, counting a default protocol when there is none,
+/// are not linked.
+const MAX_URL_LENGTH: usize = 4096;
+/// Length of the protocol assumed for URLs written without one.
+const DEFAULT_PROTOCOL_LENGTH: usize = "https://".len();
+const MAX_DOMAIN_LABEL_LENGTH: usize = 63;
+const MAX_TCO_SLUG_LENGTH: usize = 40;
+
+const INVALID_CHARS: &str = r"\x{FFFE}\x{FEFF}\x{FFFF}";
+const DIRECTIONAL_MARKERS: &str = r"\x{202A}-\x{202E}\x{061C}\x{200E}\x{200F}\x{2066}-\x{2069}";
+const SPACES: &str = r"\x{0009}-\x{000D}\x{0020}\x{0085}\x{00A0}\x{1680}\x{180E}\x{2000}-\x{200A}\x{2028}\x{2029}\x{202F}\x{205F}\x{3000}";
+const PUNCTUATION: &str = r"!'#%&\(\)*+,\\\-./:;<=>?@\[\]^_{|}~$";
+const LATIN_ACCENTS: &str = r"\x{C0}-\x{D6}\x{D8}-\x{F6}\x{F8}-\x{FF}\x{0100}-\x{024F}\x{0253}\x{0254}\x{0256}\x{0257}\x{0259}\x{025B}\x{0263}\x{0268}\x{026F}\x{0272}\x{0289}\x{028B}\x{02BB}\x{0300}-\x{036F}\x{1E00}-\x{1EFF}";
+const CYRILLIC: &str = r"\x{0400}-\x{04FF}";
+const HASHTAG_ALPHA: &str = r"\p{L}\p{M}";
+const HASHTAG_ALPHANUMERIC: &str = r"\p{L}\p{M}\p{Nd}_\x{200C}\x{200D}\x{A67E}\x{05BE}\x{05F3}\x{05F4}\x{FF5E}\x{301C}\x{309B}\x{309C}\x{30A0}\x{30FB}\x{3003}\x{0F0B}\x{0F0C}\x{00B7}";
+
+struct Patterns {
+    url: Regex,
+    ascii_domain: Regex,
+    tco: Regex,
+    hashtag: Regex,
+    hashtag_end: Regex,
+    mention: Regex,
+    mention_end: Regex,
+    reply: Regex,
+    cashtag: Regex,
+}
+
+/// Patterns matching lowercase text; like twitter-text they ignore ASCII
+/// case only, so entities are found in a copy of the text with ASCII
+/// lowercased. Byte offsets are the same in both.
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let tld_end = "(?=[^0-9a-z@]|$)";
+        let gtld = format!("(?:(?:{}){})", tld::GENERIC.join("|"), tld_end);
+        let cctld = format!("(?:(?:{}){})", tld::COUNTRY.join("|"), tld_end);
+        let punycode = "(?:xn--[-0-9a-z]+)";
+
+        let domain_char = format!(
+            "[^{}{}{}{}]",
+            PUNCTUATION, SPACES, INVALID_CHARS, DIRECTIONAL_MARKERS
+        );
+        let subdomain = format!("(?:(?:{0}(?:[_-]|{0})*)?{0}\\.)", domain_char);
+        let domain_name = format!("(?:(?:{0}(?:-|{0})*)?{0}\\.)", domain_char);
+        let domain = format!(
+            "(?:{}*{}(?:{}|{}|{}))",
+            subdomain, domain_name, gtld, cctld, punycode
+        );
+
+        let path_char = format!(
+            "[a-z{}0-9!*';:=+,.$/%#\\[\\]\\-\\x{{2013}}_~|&@{}]",
+            CYRILLIC, LATIN_ACCENTS
+        );
+        let balanced_parens = format!("\\((?:{0}+|(?:{0}*\\({0}+\\){0}*))\\)", path_char);
+        let path_end = format!(
+            "[+\\-a-z{}0-9=_#/{}]|(?:{})",
+            CYRILLIC, LATIN_ACCENTS, balanced_parens
+        );
+        let path = format!(
+            "(?:(?:{0}*(?:{1}{0}*)*{2})|(?:@{0}+/))",
+            path_char, balanced_parens, path_end
+        );
+        let query_char = r"[a-z0-9!?*'@\(\);:&=+$/%#\[\]\-_.,~|]";
+        let query_end = r"[a-z0-9\-_&=#/]";
+
+        let url = format!(
+            "(?P<before>[^a-z0-9@\\x{{FF20}}$#\\x{{FF03}}{invalid}]|[{directional}]|^)\
+             (?P<url>(?P<protocol>https?://)?(?P<domain>{domain})(?::[0-9]+)?\
+             (?P<path>/(?>{path}*))?(?:\\?{query_char}*{query_end})?)",
+            invalid = INVALID_CHARS,
+            directional = DIRECTIONAL_MARKERS,
+            domain = domain,
+            path = path,
+            query_char = query_char,
+            query_end = query_end,
+        );
+        let ascii_domain = format!(
+            "(?:(?:[-a-z0-9{}]+)\\.)+(?:{}|{}|{})",
+            LATIN_ACCENTS, gtld, cctld, punycode
+        );
+        let tco = format!(
+            "^https?://t\\.co/([a-z0-9]+)(?:\\?{}*{})?",
+            query_char, query_end
+        );
+
+        let hashtag = format!(
+            "(?:^|\\x{{FE0E}}|\\x{{FE0F}}|[^&{alnum}])(?P<sign>[#\\x{{FF03}}])(?![\\x{{FE0F}}\\x{{20E3}}])\
+             (?P<tag>[{alnum}]*[{alpha}][{alnum}]*)",
+            alnum = HASHTAG_ALPHANUMERIC,
+            alpha = HASHTAG_ALPHA,
+        );
+        let mention = "(?:^|[^a-z0-9_!#$%&*@\\x{FF20}]|(?:^|[^a-z0-9_+~.-])rt:?)\
+                       (?P<sign>[@\\x{FF20}])(?P<name>[a-z0-9_]{1,20})(?P<list>/[a-z][a-z0-9_-]{0,24})?";
+        let cashtag = format!(
+            "(?:^|[{}])\\$(?P<tag>[a-z]{{1,6}}(?:[._][a-z]{{1,2}})?)(?=$|\\s|[{}])",
+            SPACES, PUNCTUATION
+        );
+
+        Patterns {
+            url: Regex::new(&url).unwrap(),
+            ascii_domain: Regex::new(&ascii_domain).unwrap(),
+            tco: Regex::new(&tco).unwrap(),
+            hashtag: Regex::new(&hashtag).unwrap(),
+            hashtag_end: Regex::new("^(?:[#\\x{FF03}]|://)").unwrap(),
+            mention: Regex::new(mention).unwrap(),
+            mention_end: Regex::new(&format!("^(?:[@\\x{{FF20}}{}]|://)", LATIN_ACCENTS)).unwrap(),
+            reply: Regex::new(&format!(
+                "^[{}]*(?P<sign>[@\\x{{FF20}}])(?P<name>[a-z0-9_]{{1,20}})",
+                SPACES
+            ))
+            .unwrap(),
+            cashtag: Regex::new(&cashtag).unwrap(),
+        }
+    })
+}
+
+/// Extracts the mentions in `text`, with code point indices.
+///
+/// Text alone does not tell who is mentioned, so `id` is 0 and `id_str` and
+/// `name` are empty. Mentions of lists, like `@twitter/team`, are skipped.
+pub fn extract_mentions(text: &str) -> Vec<UserMention> {
+    mention_ranges(text)
+        .into_iter()
+        .map(|range| mention(text, range))
+        .collect()
+}
+
+/// A mention of a user, or of one of their lists.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MentionOrList {
+    pub screen_name: String,
+    /// The slug of the list, without the `/`, as `team` in `@twitter/team`.
+    pub list_slug: Option<String>,
+    /// Code point indices of the whole mention, list included.
+    pub indices: Vec<u64>,
+}
+
+/// Extracts the mentions of users and lists in `text`.
+pub fn extract_mentions_or_lists(text: &str) -> Vec<MentionOrList> {
+    mention_or_list_ranges(text)
+        .into_iter()
+        .map(|(range, list)| {
+            let end = list.as_ref().map_or(range.end, |list| list.end);
+            MentionOrList {
+                screen_name: without_sign(&text[range.clone()]).to_string(),
+                list_slug: list.map(|list| without_sign(&text[list]).to_string()),
+                indices: indices(text, &(range.start..end)),
+            }
+        })
+        .collect()
+}
+
+/// The screen name a tweet replies to, if it starts with a mention.
+///
+/// Only whitespace may come before the mention.
+pub fn extract_reply_screen_name(text: &str) -> Option<String> {
+    let patterns = patterns();
+    let lowercase = text.to_ascii_lowercase();
+    let captures = patterns.reply.captures(&lowercase).ok()??;
+    if followed_by(&patterns.mention_end, &lowercase, &captures) {
+        return None;
+    }
+    Some(text[captures.name("name").unwrap().range()].to_string())
+}
+
+/// Extracts the hashtags in `text`, with code point indices.
+///
+/// Hashtags are Unicode; a `#` inside a URL, as in `example.com/#about`,
+/// does not start one.
+pub fn extract_hashtags(text: &str) -> Vec<Hashtag> {
+    let urls = url_ranges(text);
+    hashtag_ranges(text)
+        .into_iter()
+        .filter(|range| {
+            !urls
+                .iter()
+                .any(|url| url.start < range.end && range.start < url.end)
+        })
+        .map(|range| hashtag(text, range))
+        .collect()
+}
+
+/// Extracts the cashtags in `text`, with code point indices.
+pub fn extract_cashtags(text: &str) -> Vec<Symbol> {
+    cashtag_ranges(text)
+        .into_iter()
+        .map(|range| cashtag(text, range))
+        .collect()
+}
+
+/// Extracts the URLs in `text`, with code point indices.
+///
+/// URLs are recognised with or without a protocol, by their top level
+/// domain. `url` is the URL as written; `display_url` and `expanded_url` are
+/// `None` until Twitter wraps it in a t.co link.
+pub fn extract_urls(text: &str) -> Vec<Url> {
+    url_ranges(text)
+        .into_iter()
+        .map(|range| url(text, range))
+        .collect()
+}
+
+/// Extracts all entities of `text`, as Twitter would when it is posted.
+///
+/// Where entities overlap, as a hashtag in a URL, the first one wins.
+/// `media` is always empty.
+pub fn extract_entities(text: &str) -> TweetEntities {
+    enum Kind {
+        Mention,
+        Hashtag,
+        Cashtag,
+        Url,
+    }
+
+    let mut found: Vec<_> = url_ranges(text)
+        .into_iter()
+        .map(|range| (range, Kind::Url))
+        .chain(
+            hashtag_ranges(text)
+                .into_iter()
+                .map(|range| (range, Kind::Hashtag)),
+        )
+        .chain(
+            mention_ranges(text)
+                .into_iter()
+                .map(|range| (range, Kind::Mention)),
+        )
+        .chain(
+            cashtag_ranges(text)
+                .into_iter()
+                .map(|range| (range, Kind::Cashtag)),
+        )
+        .collect();
+    found.sort_by_key(|(range, _)| range.start);
+
+    let mut entities = TweetEntities {
+        hashtags: Vec::new(),
+        media: Vec::new(),
+        symbols: Some(Vec::new()),
+        urls: Vec::new(),
+        user_mentions: Vec::new(),
+        #[cfg(feature = "extra-fields")]
+        extra: serde_json::Map::new(),
+    };
+    let mut end = 0;
+    for (range, kind) in found {
+        if range.start < end {
+            continue;
+        }
+        end = range.end;
+        match kind {
+            Kind::Mention => entities.user_mentions.push(mention(text, range)),
+            Kind::Hashtag => entities.hashtags.push(hashtag(text, range)),
+            Kind::Cashtag => entities
+                .symbols
+                .get_or_insert_with(Vec::new)
+                .push(cashtag(text, range)),
+            Kind::Url => entities.urls.push(url(text, range)),
+        }
+    }
+    entities
+}
+
+fn mention(text: &str, range: Range<usize>) -> UserMention {
+    UserMention {
+        id: 0,
+        id_str: String::new(),
+        indices: indices(text, &range),
+        name: String::new(),
+        screen_name: without_sign(&text[range]).to_string(),
+        #[cfg(feature = "extra-fields")]
+        extra: serde_json::Map::new(),
+    }
+}
+
+fn hashtag(text: &str, range: Range<usize>) -> Hashtag {
+    Hashtag {
+        indices: indices(text, &range),
+        text: without_sign(&text[range]).to_string(),
+        #[cfg(feature = "extra-fields")]
+        extra: serde_json::Map::new(),
+    }
+}
+
+fn cashtag(text: &str, range: Range<usize>) -> Symbol {
+    Symbol {
+        indices: indices(text, &range),
+        text: without_sign(&text[range]).to_string(),
+        #[cfg(feature = "extra-fields")]
+        extra: serde_json::Map::new(),
+    }
+}
+
+fn url(text: &str, range: Range<usize>) -> Url {
+    Url {
+        display_url: None,
+        expanded_url: None,
+        indices: indices(text, &range),
+        url: text[range].to_string(),
+        #[cfg(feature = "extra-fields")]
+        extra: serde_json::Map::new(),
+    }
+}
+
+/// Code point indices of the byte range `range` of `text`.
+fn indices(text: &str, range: &Range<usize>) -> Vec<u64> {
+    let start = text[..range.start].chars().count();
+    let end = start + text[range.clone()].chars().count();
+    vec![start as u64, end as u64]
+}
+
+/// `text` without its leading `@`, `#` or `$`, in either width.
+fn without_sign(text: &str) -> &str {
+    let mut chars = text.chars();
+    chars.next();
+    chars.as_str()
+}
+
+/// Byte ranges of the URLs in `text`, with or without a protocol.
+pub(crate) fn url_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut urls = Vec::new();
+    if !text.contains('.') {
+        return urls;
+    }
+    let patterns = patterns();
+    let text = text.to_ascii_lowercase();
+    for captures in matches(&patterns.url, &text) {
+        let url = captures.name("url").unwrap();
+        let domain = captures.name("domain").unwrap();
+        let protocol = captures.name("protocol");
+        let protocol_len = protocol.map_or(DEFAULT_PROTOCOL_LENGTH, |m| m.as_str().len());
+        if !valid_domain(domain.as_str())
+            || protocol_len + url.as_str().chars().count() > MAX_URL_LENGTH
+        {
+            continue;
+        }
+
+        if protocol.is_some() {
+            let mut end = url.end();
+            if let Ok(Some(tco)) = patterns.tco.captures(url.as_str()) {
+                if tco.get(1).unwrap().as_str().len() > MAX_TCO_SLUG_LENGTH {
+                    continue;
+                }
+                // Nothing after a t.co slug belongs to the URL.
+                end = url.start() + tco.get(0).unwrap().end();
+            }
+            urls.push(url.start()..end);
+            continue;
+        }
+
+        let before = captures.name("before").unwrap().as_str();
+        if before.ends_with(&['-', '_', '.', '/'][..]) {
+            continue;
+        }
+        // Without a protocol only ASCII domains are linked, so a domain with
+        // other characters may yield several URLs or none.
+        let mut last = None;
+        for ascii in patterns.ascii_domain.find_iter(domain.as_str()) {
+            let ascii = match ascii {
+                Ok(ascii) => ascii,
+                Err(_) => break,
+            };
+            urls.push(domain.start() + ascii.start()..domain.start() + ascii.end());
+            last = Some(urls.len() - 1);
+        }
+        // The path and query string belong to the last domain.
+        if let (Some(last), Some(_)) = (last, captures.name("path")) {
+            urls[last].end = url.end();
+        }
+    }
+    urls
+}
+
+/// Byte ranges of hashtags in `text`, `#` included.
+fn hashtag_ranges(text: &str) -> Vec<Range<usize>> {
+    let patterns = patterns();
+    let text = text.to_ascii_lowercase();
+    matches(&patterns.hashtag, &text)
+        .filter(|captures| !followed_by(&patterns.hashtag_end, &text, captures))
+        .map(|captures| captures.name("sign").unwrap().start()..captures.name("tag").unwrap().end())
+        .collect()
+}
+
+/// Byte ranges of mentions in `text`, `@` included.
+///
+/// Mentions of lists, like `@twitter/team`, are skipped.
+pub(crate) fn mention_ranges(text: &str) -> Vec<Range<usize>> {
+    mention_or_list_ranges(text)
+        .into_iter()
+        .filter(|(_, list)| list.is_none())
+        .map(|(range, _)| range)
+        .collect()
+}
+
+/// Byte ranges of mentions in `text`, `@` included, each with the range of
+/// the list it names, `/` included.
+fn mention_or_list_ranges(text: &str) -> Vec<(Range<usize>, Option<Range<usize>>)> {
+    let patterns = patterns();
+    let text = text.to_ascii_lowercase();
+    matches(&patterns.mention, &text)
+        .filter(|captures| !followed_by(&patterns.mention_end, &text, captures))
+        .map(|captures| {
+            let range =
+                captures.name("sign").unwrap().start()..captures.name("name").unwrap().end();
+            (range, captures.name("list").map(|list| list.range()))
+        })
+        .collect()
+}
+
+/// Byte ranges of cashtags in `text`, `$` included.
+fn cashtag_ranges(text: &str) -> Vec<Range<usize>> {
+    let text = text.to_ascii_lowercase();
+    matches(&patterns().cashtag, &text)
+        .map(|captures| {
+            let tag = captures.name("tag").unwrap();
+            tag.start() - 1..tag.end()
+        })
+        .collect()
+}
+
+/// Successive matches of `pattern`, ending early if it backtracks too much,
+/// which only pathological input does.
+fn matches<'t>(pattern: &'t Regex, text: &'t str) -> impl Iterator<Item = Captures<'t>> {
+    pattern.captures_iter(text).map_while(Result::ok)
+}
+
+/// Whether the text after a match rules it out.
+fn followed_by(end: &Regex, text: &str, captures: &Captures) -> bool {
+    let rest = &text[captures.get(0).unwrap().end()..];
+    matches!(end.is_match(rest), Ok(true))
+}
+
+/// Whether every label of `domain` would fit a DNS label once encoded.
+fn valid_domain(domain: &str) -> bool {
+    if domain.starts_with("xn--") && !domain.is_ascii() {
+        return false;
+    }
+    domain
+        .split('.')
+        .map(punycode_len)
+        .all(|len| len > 0 && len <= MAX_DOMAIN_LABEL_LENGTH)
+}
+
+/// Length of the ASCII form of a domain label, `xn--` prefix included.
+///
+/// Follows the encoder of RFC 3492 without producing any output.
+fn punycode_len(label: &str) -> usize {
+    const BASE: u64 = 36;
+    const T_MIN: u64 = 1;
+    const T_MAX: u64 = 26;
+
+    fn adapt(delta: u64, points: u64, first: bool) -> u64 {
+        let mut delta = if first { delta / 700 } else { delta / 2 };
+        delta += delta / points;
+        let mut k = 0;
+        while delta > (BASE - T_MIN) * T_MAX / 2 {
+            delta /= BASE - T_MIN;
+            k += BASE;
+        }
+        k + (BASE - T_MIN + 1) * delta / (delta + 38)
+    }
+
+    if label.is_ascii() {
+        return label.len();
+    }
+    let input: Vec<u64> = label.chars().map(|c| c as u64).collect();
+    let basic = input.iter().filter(|&&c| c < 0x80).count();
+    let mut len = "xn--".len() + basic + if basic > 0 { 1 } else { 0 };
+
+    let (mut n, mut delta, mut bias) = (0x80, 0, 72);
+    let mut handled = basic;
+    while handled < input.len() {
+        let m = input.iter().cloned().filter(|&c| c >= n).min().unwrap();
+        delta += (m - n) * (handled as u64 + 1);
+        n = m;
+        for &c in &input {
+            if c < n {
+                delta += 1;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = if k <= bias {
+                        T_MIN
+                    } else if k >= bias + T_MAX {
+                        T_MAX
+                    } else {
+                        k - bias
+                    };
+                    if q < t {
+                        break;
+                    }
+                    len += 1;
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                len += 1;
+                bias = adapt(delta, handled as u64 + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_yaml::Value;
+    use std::fs;
+    use std::path::Path;
+
+    /// Runs a section of the twitter-text conformance suite's extract.yml.
+    ///
+    /// `extract` returns each entity's text and indices; expected entities
+    /// are plain strings, or maps holding the text under `key` and indices.
+    /// The `replies` section is run by its own test, as it expects a single
+    /// screen name rather than a list of entities.
+    fn conformance<F>(section: &str, key: &str, extract: F)
+    where
+        F: Fn(&str) -> Vec<(String, Vec<u64>)>,
+    {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/twitter-text/extract.yml");
+        let suite: Value = serde_yaml::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        let cases = suite["tests"][section].as_sequence().unwrap();
+        assert!(!cases.is_empty());
+        for case in cases {
+            let found = extract(case["text"].as_str().unwrap());
+            let expected = case["expected"].as_sequence().unwrap();
+            let description = case["description"].as_str().unwrap();
+            if expected.iter().all(Value::is_string) {
+                let found: Vec<_> = found.into_iter().map(|(text, _)| text).collect();
+                let expected: Vec<_> = expected.iter().map(|e| e.as_str().unwrap()).collect();
+                assert_eq!(found, expected, "{}", description);
+            } else {
+                let expected: Vec<_> = expected
+                    .iter()
+                    .map(|e| {
+                        let indices = e["indices"].as_sequence().unwrap();
+                        // Lists are expected apart from their owner, as `/slug`.
+                        let list = e["list_slug"].as_str().unwrap_or_default();
+                        (
+                            format!("{}{}", e[key].as_str().unwrap(), list),
+                            indices.iter().map(|i| i.as_u64().unwrap()).collect(),
+                        )
+                    })
+                    .collect();
+                assert_eq!(found, expected, "{}", description);
+            }
+        }
+    }
+
+    #[test]
+    fn conformance_mentions() {
+        let extract = |text: &str| {
+            extract_mentions(text)
+                .into_iter()
+                .map(|mention| (mention.screen_name, mention.indices))
+                .collect()
+        };
+        conformance("mentions", "screen_name", extract);
+        conformance("mentions_with_indices", "screen_name", extract);
+    }
+
+    #[test]
+    fn conformance_mentions_or_lists() {
+        let extract = |text: &str| {
+            extract_mentions_or_lists(text)
+                .into_iter()
+                .map(|mention| {
+                    let slug = mention.list_slug.map(|slug| format!("/{}", slug));
+                    let key = format!("{}{}", mention.screen_name, slug.unwrap_or_default());
+                    (key, mention.indices)
+                })
+                .collect()
+        };
+        conformance("mentions_or_lists_with_indices", "screen_name", extract);
+    }
+
+    #[test]
+    fn conformance_replies() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/twitter-text/extract.yml");
+        let suite: Value = serde_yaml::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        let cases = suite["tests"]["replies"].as_sequence().unwrap();
+        assert!(!cases.is_empty());
+        for case in cases {
+            assert_eq!(
+                extract_reply_screen_name(case["text"].as_str().unwrap()).as_deref(),
+                case["expected"].as_str(),
+                "{}",
+                case["description"].as_str().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn conformance_hashtags() {
+        let extract = |text: &str| {
+            extract_hashtags(text)
+                .into_iter()
+                .map(|hashtag| (hashtag.text, hashtag.indices))
+                .collect()
+        };
+        conformance("hashtags", "hashtag", extract);
+        conformance("hashtags_from_astral", "hashtag", extract);
+        conformance("hashtags_with_indices", "hashtag", extract);
+    }
+
+    #[test]
+    fn conformance_cashtags() {
+        let extract = |text: &str| {
+            extract_cashtags(text)
+                .into_iter()
+                .map(|symbol| (symbol.text, symbol.indices))
+                .collect()
+        };
+        conformance("cashtags", "cashtag", extract);
+        conformance("cashtags_with_indices", "cashtag", extract);
+    }
+
+    #[test]
+    fn conformance_urls() {
+        let extract = |text: &str| {
+            extract_urls(text)
+                .into_iter()
+                .map(|url| (url.url, url.indices))
+                .collect()
+        };
+        conformance("urls", "url", extract);
+        conformance("urls_with_indices", "url", extract);
+        conformance("urls_with_directional_markers", "url", extract);
+        conformance("tco_urls_with_params", "url", extract);
+    }
+
+    #[test]
+    fn entities_use_code_points() {
+        let entities = extract_entities("🎉 @jack ＃ünï $TWTR http://example.com/#top");
+        assert_eq!(entities.user_mentions[0].screen_name, "jack");
+        assert_eq!(entities.user_mentions[0].indices, vec![2, 7]);
+        assert_eq!(entities.hashtags[0].text, "ünï");
+        assert_eq!(entities.hashtags[0].indices, vec![8, 12]);
+        assert_eq!(entities.symbols.unwrap()[0].indices, vec![13, 18]);
+        // The hashtag in the URL is not extracted.
+        assert_eq!(entities.hashtags.len(), 1);
+        assert_eq!(entities.urls[0].url, "http://example.com/#top");
+        assert_eq!(entities.urls[0].indices, vec![19, 42]);
+    }
+
+    fn urls(text: &str) -> Vec<&str> {
+        url_ranges(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect()
+    }
+
+    #[test]
+    fn punycode_lengths() {
+        assert_eq!(punycode_len("twitter"), 7);
+        // xn--bcher-kva
+        assert_eq!(punycode_len("bücher"), 13);
+        // xn--wgv71a119e
+        assert_eq!(punycode_len("日本語"), 14);
+    }
+
+    #[test]
+    fn finds_urls() {
+        assert_eq!(
+            urls("see http://example.com/a_(b) and t.co/abc, example.com."),
+            vec!["http://example.com/a_(b)", "t.co/abc", "example.com"]
+        );
+        assert_eq!(
+            urls("https://t.co/abcdefg/extra"),
+            vec!["https://t.co/abcdefg"]
+        );
+        assert_eq!(
+            urls("Visit HTTPS://Example.COM!"),
+            vec!["HTTPS://Example.COM"]
+        );
+        // Without a protocol, only the ASCII parts of a domain are linked.
+        assert_eq!(
+            urls("twitter.jp日本語it.so/path"),
+            vec!["twitter.jp", "it.so/path"]
+        );
+        assert!(urls("@user.com #tag.com $cash.com").is_empty());
+    }
+
+    #[test]
+    fn rejects_overlong_labels() {
+        let label = "a".repeat(64);
+        assert!(urls(&format!("{}.com", label)).is_empty());
+        assert_eq!(urls(&format!("{}.com", &label[1..])).len(), 1);
+    }
+}
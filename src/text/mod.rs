@@ -0,0 +1,64 @@
+//! Working with tweet text and its entities.
+
+mod extract;
+mod render;
+mod segment;
+mod split;
+mod tld;
+mod validate;
+
+pub use self::extract::{
+    extract_cashtags, extract_entities, extract_hashtags, extract_mentions,
+    extract_mentions_or_lists, extract_reply_screen_name, extract_urls, MentionOrList,
+};
+pub use self::render::{to_html, to_plain_text};
+pub use self::segment::{Entity, IndexError, Segment, Segments};
+pub use self::split::{split_thread, split_thread_numbered};
+pub use self::validate::{
+    parse_tweet, validate_tweet, Config, ParseResults, Validation, WeightedRange,
+};
+
+use std::borrow::Cow;
+
+/// Reverses the HTML escaping Twitter applies to `Tweet::text`.
+///
+/// Twitter escapes `&`, `<` and `>` in tweet text, but entity indices refer
+/// to the unescaped text, so unescape before segmenting a tweet from the API.
+pub fn unescape(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('&') {
+        unescaped.push_str(&rest[..at]);
+        rest = &rest[at..];
+        let (decoded, len) = if rest.starts_with("&amp;") {
+            ('&', 5)
+        } else if rest.starts_with("&lt;") {
+            ('<', 4)
+        } else if rest.starts_with("&gt;") {
+            ('>', 4)
+        } else {
+            ('&', 1)
+        };
+        unescaped.push(decoded);
+        rest = &rest[len..];
+    }
+    unescaped.push_str(rest);
+    Cow::Owned(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unescape_entities() {
+        assert_eq!(unescape("fish &amp; chips"), "fish & chips");
+        assert_eq!(unescape("&lt;3 &gt;_&lt;"), "<3 >_<");
+        assert_eq!(unescape("AT&T &amp;amp;"), "AT&T &amp;");
+        assert!(matches!(unescape("no entities"), Cow::Borrowed(_)));
+    }
+}
@@ -0,0 +1,254 @@
+use super::{unescape, Entity, IndexError, Segment, Segments};
+use crate::api::{Tweet, Url};
+use std::fmt::Write;
+
+/// Renders a tweet to HTML the way twitter.com displays it.
+///
+/// Text is escaped; mentions, hashtags and cashtags link to twitter.com;
+/// t.co links are replaced by their `display_url` linking to `expanded_url`;
+/// media links and the link to a quoted tweet are removed, since those are
+/// displayed as attachments.
+///
+/// Renders the retweet itself for retweets; pass `retweeted_status` to
+/// render the original tweet instead.
+pub fn to_html(tweet: &Tweet) -> Result<String, IndexError> {
+    render(tweet, |html, segment| match segment.entity {
+        None => escape_into(html, segment.text),
+        Some(Entity::Mention(mention)) => link(
+            html,
+            &format!("https://twitter.com/{}", mention.screen_name),
+            segment.text,
+        ),
+        Some(Entity::Hashtag(hashtag)) => link(
+            html,
+            &format!(
+                "https://twitter.com/hashtag/{}?src=hash",
+                percent_encode(&hashtag.text)
+            ),
+            segment.text,
+        ),
+        Some(Entity::Cashtag(symbol)) => link(
+            html,
+            &format!(
+                "https://twitter.com/search?q=%24{}&src=ctag",
+                percent_encode(&symbol.text)
+            ),
+            segment.text,
+        ),
+        Some(Entity::Url(url)) => link(
+            html,
+            url.expanded_url.as_ref().unwrap_or(&url.url),
+            url.display_url.as_ref().unwrap_or(&url.url),
+        ),
+        Some(Entity::Media(_)) => {}
+    })
+}
+
+/// Renders a tweet to plain text with links expanded.
+///
+/// t.co links are replaced by their `expanded_url`; media links and the link
+/// to a quoted tweet are removed, as in [`to_html`].
+pub fn to_plain_text(tweet: &Tweet) -> Result<String, IndexError> {
+    render(tweet, |text, segment| match segment.entity {
+        Some(Entity::Url(url)) => text.push_str(url.expanded_url.as_ref().unwrap_or(&url.url)),
+        Some(Entity::Media(_)) => {}
+        _ => text.push_str(segment.text),
+    })
+}
+
+fn render<F>(tweet: &Tweet, mut write: F) -> Result<String, IndexError>
+where
+    F: FnMut(&mut String, &Segment),
+{
+    let text = unescape(tweet.body());
+    let segments: Vec<_> = Segments::new(&text, &tweet.entities)?.collect();
+    let last = segments.len().saturating_sub(1);
+
+    let mut rendered = String::with_capacity(text.len());
+    let mut trim = false;
+    for (i, segment) in segments.iter().enumerate() {
+        let hidden = match segment.entity {
+            Some(Entity::Media(_)) => true,
+            Some(Entity::Url(url)) => i == last && quotes(tweet, url),
+            _ => false,
+        };
+        if hidden {
+            trim = true;
+        } else {
+            write(&mut rendered, segment);
+        }
+    }
+    if trim {
+        rendered.truncate(rendered.trim_end().len());
+    }
+    Ok(rendered)
+}
+
+/// Whether `url` links to the tweet quoted by `tweet`.
+fn quotes(tweet: &Tweet, url: &Url) -> bool {
+    match (&tweet.quoted_status, &url.expanded_url) {
+        (Some(quoted), Some(expanded)) => expanded.ends_with(&format!("/status/{}", quoted.id_str)),
+        _ => false,
+    }
+}
+
+fn link(html: &mut String, href: &str, text: &str) {
+    html.push_str("<a href=\"");
+    escape_into(html, href);
+    html.push_str("\">");
+    escape_into(html, text);
+    html.push_str("</a>");
+}
+
+fn escape_into(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+}
+
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => write!(encoded, "%{:02X}", byte).unwrap(),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{Hashtag, Media};
+    use std::fs;
+    use std::path::Path;
+
+    fn fixture(name: &str) -> Tweet {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/tweets")
+            .join(name);
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn media(indices: Vec<u64>) -> Media {
+        serde_json::from_value(serde_json::json!({
+            "display_url": "pic.twitter.com/HtdvV0bPEu",
+            "expanded_url": "http://twitter.com/Interior/status/504032379045179393/photo/1",
+            "id": 504032378411446273u64,
+            "id_str": "504032378411446273",
+            "indices": indices,
+            "media_url": "http://pbs.twimg.com/media/Bv6uxxaCcAEjWHD.jpg",
+            "media_url_https": "https://pbs.twimg.com/media/Bv6uxxaCcAEjWHD.jpg",
+            "sizes": {
+                "large": {"h": 695, "resize": "fit", "w": 1024},
+                "medium": {"h": 407, "resize": "fit", "w": 600},
+                "small": {"h": 230, "resize": "fit", "w": 340},
+                "thumb": {"h": 150, "resize": "crop", "w": 150}
+            },
+            "type": "photo",
+            "url": "http://t.co/HtdvV0bPEu"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn html_links_entities() {
+        let tweet = fixture("hashtags_and_cashtags.json");
+        assert_eq!(
+            to_html(&tweet).unwrap(),
+            "Earnings call starts now \
+             <a href=\"https://twitter.com/hashtag/TWTR?src=hash\">#TWTR</a> \
+             <a href=\"https://twitter.com/search?q=%24TWTR&amp;src=ctag\">$TWTR</a> \
+             &amp; more at \
+             <a href=\"https://investor.twitterinc.com\">investor.twitterinc.com</a>"
+        );
+
+        let tweet = fixture("reply.json");
+        assert_eq!(
+            to_html(&tweet).unwrap(),
+            "<a href=\"https://twitter.com/TwitterDev\">@TwitterDev</a> \
+             <a href=\"https://twitter.com/TwitterAPI\">@TwitterAPI</a> \
+             thanks for the heads up!"
+        );
+    }
+
+    #[test]
+    fn html_escapes_text() {
+        let mut tweet = fixture("plain_text.json");
+        tweet.text = Some("&lt;script&gt;alert(\"hi\")&lt;/script&gt; #café".to_string());
+        tweet.entities.hashtags = vec![Hashtag {
+            indices: vec![29, 34],
+            text: "café".to_string(),
+            #[cfg(feature = "extra-fields")]
+            extra: serde_json::Map::new(),
+        }];
+        assert_eq!(
+            to_html(&tweet).unwrap(),
+            "&lt;script&gt;alert(&quot;hi&quot;)&lt;/script&gt; \
+             <a href=\"https://twitter.com/hashtag/caf%C3%A9?src=hash\">#café</a>"
+        );
+    }
+
+    #[test]
+    fn media_links_are_removed() {
+        let mut tweet = fixture("plain_text.json");
+        tweet.text = Some("Waving bear @LakeClarkNPS http://t.co/HtdvV0bPEu".to_string());
+        tweet.entities.media = vec![media(vec![26, 48])];
+        assert_eq!(to_html(&tweet).unwrap(), "Waving bear @LakeClarkNPS");
+        assert_eq!(to_plain_text(&tweet).unwrap(), "Waving bear @LakeClarkNPS");
+    }
+
+    #[test]
+    fn plain_text_expands_links() {
+        let tweet = fixture("hashtags_and_cashtags.json");
+        assert_eq!(
+            to_plain_text(&tweet).unwrap(),
+            "Earnings call starts now #TWTR $TWTR & more at https://investor.twitterinc.com"
+        );
+
+        let tweet = fixture("legacy_url_entity.json");
+        assert_eq!(
+            to_plain_text(&tweet).unwrap(),
+            "Reading http://bit.ly/aHXT3Y while the build runs"
+        );
+        assert_eq!(
+            to_html(&tweet).unwrap(),
+            "Reading <a href=\"http://bit.ly/aHXT3Y\">http://bit.ly/aHXT3Y</a> while the build runs"
+        );
+    }
+
+    #[test]
+    fn quoted_tweet_link_is_removed() {
+        let quoted = fixture("plain_text.json");
+        let mut tweet = fixture("quote_of_deleted_tweet.json");
+        // The quoted tweet was deleted, so twitter.com keeps the link.
+        assert_eq!(
+            to_plain_text(&tweet).unwrap(),
+            "This aged well https://twitter.com/someone/status/1084906538462851072"
+        );
+
+        tweet.entities.urls[0].expanded_url = Some(format!(
+            "https://twitter.com/TwitterDev/status/{}",
+            quoted.id_str
+        ));
+        tweet.quoted_status = Some(Box::new(quoted));
+        assert_eq!(to_plain_text(&tweet).unwrap(), "This aged well");
+    }
+
+    #[test]
+    fn invalid_indices_are_reported() {
+        let mut tweet = fixture("reply.json");
+        tweet.entities.user_mentions[1].indices = vec![12, 100];
+        assert!(to_html(&tweet).is_err());
+    }
+}
@@ -0,0 +1,377 @@
+use crate::api::{Hashtag, Media, Symbol, TweetEntities, Url, UserMention};
+use std::error;
+use std::fmt;
+
+/// An entity of a tweet, borrowed from its `TweetEntities`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Entity<'a> {
+    Mention(&'a UserMention),
+    Hashtag(&'a Hashtag),
+    Cashtag(&'a Symbol),
+    Url(&'a Url),
+    Media(&'a Media),
+}
+
+impl<'a> Entity<'a> {
+    /// The `[start, end)` code point offsets of the entity in the tweet text.
+    pub fn indices(&self) -> &'a [u64] {
+        match self {
+            Entity::Mention(mention) => &mention.indices,
+            Entity::Hashtag(hashtag) => &hashtag.indices,
+            Entity::Cashtag(symbol) => &symbol.indices,
+            Entity::Url(url) => &url.indices,
+            Entity::Media(media) => &media.indices,
+        }
+    }
+}
+
+/// A run of tweet text, either plain or covered by an entity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment<'a> {
+    pub text: &'a str,
+    /// Code point offset of the first character.
+    pub start: usize,
+    /// Code point offset one past the last character.
+    pub end: usize,
+    /// `None` for plain text.
+    pub entity: Option<Entity<'a>>,
+}
+
+/// Entity indices that do not fit the text they were given with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IndexError {
+    /// Indices are not a `[start, end]` pair with `start < end`.
+    Malformed { indices: Vec<u64> },
+    /// The entity ends after the text does.
+    OutOfRange { indices: Vec<u64>, len: usize },
+    /// The entity starts before the previous one ended.
+    Overlap {
+        indices: Vec<u64>,
+        previous: Vec<u64>,
+    },
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IndexError::Malformed { indices } => write!(f, "malformed indices {:?}", indices),
+            IndexError::OutOfRange { indices, len } => write!(
+                f,
+                "indices {:?} out of range for text of {} characters",
+                indices, len
+            ),
+            IndexError::Overlap { indices, previous } => {
+                write!(f, "indices {:?} overlap {:?}", indices, previous)
+            }
+        }
+    }
+}
+
+impl error::Error for IndexError {}
+
+struct Span<'a> {
+    start: usize,
+    end: usize,
+    start_byte: usize,
+    end_byte: usize,
+    entity: Entity<'a>,
+}
+
+/// Iterator splitting tweet text into plain text and entity [`Segment`]s.
+///
+/// Indices are Unicode code points, as in the v1.1 API, not bytes or UTF-16
+/// units. Text from the API must be [`unescape`](super::unescape)d first.
+///
+/// ```
+/// use twitter::text::{extract_entities, Segments};
+///
+/// let text = "héllo @TwitterAPI!";
+/// let entities = extract_entities(text);
+///
+/// let segments: Vec<_> = Segments::new(text, &entities)
+///     .unwrap()
+///     .map(|segment| (segment.text, segment.entity.is_some()))
+///     .collect();
+/// assert_eq!(
+///     segments,
+///     vec![("héllo ", false), ("@TwitterAPI", true), ("!", false)]
+/// );
+/// ```
+pub struct Segments<'a> {
+    text: &'a str,
+    spans: std::vec::IntoIter<Span<'a>>,
+    next: Option<Span<'a>>,
+    /// Code point and byte offset of the next unconsumed character.
+    position: (usize, usize),
+    len: usize,
+}
+
+impl<'a> Segments<'a> {
+    /// Segments `text` by `entities`, checking all indices up front.
+    ///
+    /// Media entities are taken from `entities.media`; all media of a tweet
+    /// share one link, so `extended_entities` adds nothing here.
+    pub fn new(text: &'a str, entities: &'a TweetEntities) -> Result<Segments<'a>, IndexError> {
+        let mut found: Vec<Entity<'a>> = Vec::new();
+        found.extend(entities.user_mentions.iter().map(Entity::Mention));
+        found.extend(entities.hashtags.iter().map(Entity::Hashtag));
+        found.extend(entities.symbols.iter().flatten().map(Entity::Cashtag));
+        found.extend(entities.urls.iter().map(Entity::Url));
+        found.extend(entities.media.iter().map(Entity::Media));
+        Segments::from_entities(text, found)
+    }
+
+    /// Segments `text` by an arbitrary list of entities.
+    pub fn from_entities(
+        text: &'a str,
+        mut entities: Vec<Entity<'a>>,
+    ) -> Result<Segments<'a>, IndexError> {
+        for entity in &entities {
+            match *entity.indices() {
+                [start, end] if start < end => {}
+                _ => {
+                    return Err(IndexError::Malformed {
+                        indices: entity.indices().to_vec(),
+                    })
+                }
+            }
+        }
+        entities.sort_by_key(|entity| entity.indices()[0]);
+
+        let mut spans = Vec::with_capacity(entities.len());
+        let mut chars = text.char_indices().enumerate().peekable();
+        let mut byte_at = |offset: usize| -> Option<usize> {
+            while let Some(&(index, (byte, _))) = chars.peek() {
+                if index == offset {
+                    return Some(byte);
+                }
+                chars.next();
+            }
+            None
+        };
+        let len = text.chars().count();
+        let mut previous: Option<&[u64]> = None;
+        for entity in entities {
+            let indices = entity.indices();
+            let (start, end) = (indices[0] as usize, indices[1] as usize);
+            if end > len {
+                return Err(IndexError::OutOfRange {
+                    indices: indices.to_vec(),
+                    len,
+                });
+            }
+            if let Some(previous) = previous {
+                if (start as u64) < previous[1] {
+                    return Err(IndexError::Overlap {
+                        indices: indices.to_vec(),
+                        previous: previous.to_vec(),
+                    });
+                }
+            }
+            previous = Some(indices);
+
+            let start_byte = byte_at(start).unwrap_or(text.len());
+            let end_byte = byte_at(end).unwrap_or(text.len());
+            spans.push(Span {
+                start,
+                end,
+                start_byte,
+                end_byte,
+                entity,
+            });
+        }
+
+        let mut spans = spans.into_iter();
+        Ok(Segments {
+            text,
+            next: spans.next(),
+            spans,
+            position: (0, 0),
+            len,
+        })
+    }
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Segment<'a>> {
+        let (start, start_byte) = self.position;
+        match self.next.take() {
+            Some(span) if span.start == start => {
+                self.position = (span.end, span.end_byte);
+                self.next = self.spans.next();
+                Some(Segment {
+                    text: &self.text[span.start_byte..span.end_byte],
+                    start,
+                    end: span.end,
+                    entity: Some(span.entity),
+                })
+            }
+            Some(span) => {
+                self.position = (span.start, span.start_byte);
+                let segment = Segment {
+                    text: &self.text[start_byte..span.start_byte],
+                    start,
+                    end: span.start,
+                    entity: None,
+                };
+                self.next = Some(span);
+                Some(segment)
+            }
+            None if start < self.len => {
+                self.position = (self.len, self.text.len());
+                Some(Segment {
+                    text: &self.text[start_byte..],
+                    start,
+                    end: self.len,
+                    entity: None,
+                })
+            }
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Tweet;
+    use crate::text::unescape;
+    use std::fs;
+    use std::path::Path;
+
+    fn fixture(name: &str) -> Tweet {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/tweets")
+            .join(name);
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn no_entities() -> TweetEntities {
+        TweetEntities {
+            hashtags: Vec::new(),
+            media: Vec::new(),
+            symbols: Some(Vec::new()),
+            urls: Vec::new(),
+            user_mentions: Vec::new(),
+            #[cfg(feature = "extra-fields")]
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    fn hashtag(text: &str, indices: Vec<u64>) -> Hashtag {
+        Hashtag {
+            indices,
+            text: text.to_string(),
+            #[cfg(feature = "extra-fields")]
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    #[test]
+    fn segments_tweet() {
+        let tweet = fixture("hashtags_and_cashtags.json");
+        let text = unescape(tweet.body());
+        let segments: Vec<_> = Segments::new(&text, &tweet.entities).unwrap().collect();
+
+        let texts: Vec<_> = segments.iter().map(|segment| segment.text).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Earnings call starts now ",
+                "#TWTR",
+                " ",
+                "$TWTR",
+                " & more at ",
+                "https://t.co/8IkCzCDr19",
+            ]
+        );
+        assert_eq!(
+            segments[1].entity,
+            Some(Entity::Hashtag(&tweet.entities.hashtags[0]))
+        );
+        assert_eq!(
+            segments[3].entity,
+            Some(Entity::Cashtag(
+                &tweet.entities.symbols.as_ref().unwrap()[0]
+            ))
+        );
+        assert_eq!(
+            segments[5].entity,
+            Some(Entity::Url(&tweet.entities.urls[0]))
+        );
+        assert_eq!((segments[5].start, segments[5].end), (47, 70));
+    }
+
+    #[test]
+    fn segments_by_code_points() {
+        // 🎉 is one code point but two UTF-16 units and four bytes.
+        let tweet = fixture("retweet.json");
+        let segments: Vec<_> = Segments::new(tweet.body(), &tweet.entities)
+            .unwrap()
+            .collect();
+        assert_eq!(segments.len(), 4);
+        assert_eq!(segments[1].text, "@TwitterDev");
+        assert_eq!(
+            segments[2].text,
+            ": Check out the new Twitter API docs, now with more examples 🎉 "
+        );
+        assert_eq!(segments[3].text, "https://t.co/ZRk8vC6J2B");
+    }
+
+    #[test]
+    fn adjacent_entities_and_plain_text() {
+        let mut entities = no_entities();
+        entities.hashtags = vec![hashtag("a", vec![0, 2]), hashtag("b", vec![2, 4])];
+        let texts: Vec<_> = Segments::new("#a#b", &entities)
+            .unwrap()
+            .map(|segment| segment.text)
+            .collect();
+        assert_eq!(texts, vec!["#a", "#b"]);
+
+        let none = no_entities();
+        let segments: Vec<_> = Segments::new("", &none).unwrap().collect();
+        assert!(segments.is_empty());
+
+        let segments: Vec<_> = Segments::new("plain", &none).unwrap().collect();
+        assert_eq!(
+            segments,
+            vec![Segment {
+                text: "plain",
+                start: 0,
+                end: 5,
+                entity: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_indices() {
+        let mut entities = no_entities();
+        entities.hashtags = vec![hashtag("a", vec![3])];
+        assert_eq!(
+            Segments::new("#a", &entities).err(),
+            Some(IndexError::Malformed { indices: vec![3] })
+        );
+
+        // Five code points, even though "ünï" is more bytes.
+        entities.hashtags = vec![hashtag("ünï", vec![1, 6])];
+        assert_eq!(
+            Segments::new(" #ünï", &entities).err(),
+            Some(IndexError::OutOfRange {
+                indices: vec![1, 6],
+                len: 5,
+            })
+        );
+
+        entities.hashtags = vec![hashtag("ab", vec![0, 3]), hashtag("b", vec![2, 4])];
+        assert_eq!(
+            Segments::new("#ab#b", &entities).err(),
+            Some(IndexError::Overlap {
+                indices: vec![2, 4],
+                previous: vec![0, 3],
+            })
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Splitting long text into a thread.
+
+use super::extract;
+use super::validate::{parse_tweet, Config};
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Characters ending a sentence.
+const TERMINATORS: [char; 7] = ['.', '!', '?', '…', '。', '！', '？'];
+
+/// Where a chunk may end, in increasing order of preference.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Break {
+    Grapheme,
+    Word,
+    Sentence,
+}
+
+/// Splits `text` into tweets within `config`'s limit.
+///
+/// Tweets end at a sentence if that keeps them at least half full, and
+/// otherwise at a word. Only a word too long for a tweet of its own is split
+/// inside, between grapheme clusters. URLs and mentions are never split.
+/// Whitespace between tweets is dropped.
+pub fn split_thread(text: &str, config: &Config) -> Vec<String> {
+    split(text, config, "")
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+/// As [`split_thread`], but ends every tweet with its position, like
+/// `" 2/5"`, leaving room for it in the limit.
+pub fn split_thread_numbered(text: &str, config: &Config) -> Vec<String> {
+    let mut digits = 1;
+    loop {
+        // Room for the widest numbering with this many digits.
+        let nines = "9".repeat(digits);
+        let chunks = split(text, config, &format!(" {}/{}", nines, nines));
+        let total = chunks.len();
+        if total.to_string().len() <= digits {
+            return chunks
+                .into_iter()
+                .enumerate()
+                .map(|(i, chunk)| format!("{} {}/{}", chunk, i + 1, total))
+                .collect();
+        }
+        digits += 1;
+    }
+}
+
+/// Splits `text` into chunks that fit the limit with `suffix` appended.
+fn split<'t>(text: &'t str, config: &Config, suffix: &str) -> Vec<&'t str> {
+    let breaks = breaks(text);
+    let fits = |chunk: &str| {
+        let weighted = parse_tweet(&format!("{}{}", chunk, suffix), config).weighted_length;
+        weighted <= config.max_weighted_tweet_length
+    };
+    let half = |chunk: &str| {
+        parse_tweet(chunk, config).weighted_length * 2 >= config.max_weighted_tweet_length
+    };
+
+    let mut chunks = Vec::new();
+    let mut start = skip_whitespace(text, 0);
+    while start < text.len() {
+        let next = breaks.iter().position(|&(end, _)| end > start).unwrap();
+        let candidates = &breaks[next..];
+        // Words first, graphemes only when not even one word fits.
+        let end = last_fitting(text, start, candidates, Break::Word, fits)
+            .map(|(word, sentence)| match sentence {
+                Some(sentence) if half(text[start..sentence].trim_end()) => sentence,
+                _ => word,
+            })
+            .or_else(|| {
+                last_fitting(text, start, candidates, Break::Grapheme, fits).map(|(end, _)| end)
+            })
+            // An unbreakable piece over the limit gets a tweet of its own.
+            .unwrap_or(candidates[0].0);
+        chunks.push(text[start..end].trim_end());
+        start = skip_whitespace(text, end);
+    }
+    chunks
+}
+
+/// The last break of at least `kind` whose chunk fits, and the last
+/// sentence break that fits.
+fn last_fitting<F>(
+    text: &str,
+    start: usize,
+    breaks: &[(usize, Break)],
+    kind: Break,
+    fits: F,
+) -> Option<(usize, Option<usize>)>
+where
+    F: Fn(&str) -> bool,
+{
+    let mut last = None;
+    let mut sentence = None;
+    for &(end, found) in breaks.iter().filter(|&&(_, found)| found >= kind) {
+        if !fits(text[start..end].trim_end()) {
+            break;
+        }
+        last = Some(end);
+        if found == Break::Sentence {
+            sentence = Some(end);
+        }
+    }
+    last.map(|last| (last, sentence))
+}
+
+/// Byte offsets a chunk may end at, with the best kind of break at each.
+///
+/// Word and sentence breaks are after whitespace, so the chunk before them
+/// is trimmed; CJK full stops break without it.
+fn breaks(text: &str) -> Vec<(usize, Break)> {
+    let mut protected: Vec<Range<usize>> = extract::url_ranges(text);
+    protected.extend(extract::mention_ranges(text));
+    let inside = |offset: usize| {
+        protected
+            .iter()
+            .any(|range| range.start < offset && offset < range.end)
+    };
+
+    let mut breaks = Vec::new();
+    let mut previous: Option<&str> = None;
+    let mut terminated = false;
+    for (offset, grapheme) in text.grapheme_indices(true) {
+        if let Some(previous) = previous {
+            let space = previous.chars().all(char::is_whitespace);
+            let kind = if !space && grapheme.chars().all(char::is_whitespace) {
+                None
+            } else if space && (terminated || previous.contains('\n')) {
+                Some(Break::Sentence)
+            } else if space {
+                Some(Break::Word)
+            } else if previous.ends_with(&['。', '！', '？'][..]) {
+                Some(Break::Sentence)
+            } else {
+                Some(Break::Grapheme)
+            };
+            if let Some(kind) = kind.filter(|_| !inside(offset)) {
+                breaks.push((offset, kind));
+            }
+            if !space {
+                terminated = previous.ends_with(&TERMINATORS[..]);
+            } else if previous.contains('\n') {
+                terminated = true;
+            }
+        }
+        previous = Some(grapheme);
+    }
+    breaks.push((text.len(), Break::Sentence));
+    breaks
+}
+
+fn skip_whitespace(text: &str, from: usize) -> usize {
+    text[from..]
+        .find(|c: char| !c.is_whitespace())
+        .map_or(text.len(), |at| from + at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small() -> Config {
+        Config {
+            max_weighted_tweet_length: 40,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn splits_at_sentences_then_words() {
+        let text =
+            "The release is out today. It brings threads, media uploads and streaming to everyone.";
+        assert_eq!(
+            split_thread(text, &small()),
+            vec![
+                "The release is out today.",
+                "It brings threads, media uploads and",
+                "streaming to everyone.",
+            ]
+        );
+        assert_eq!(split_thread("  short  ", &small()), vec!["short"]);
+        assert!(split_thread(" \n ", &small()).is_empty());
+    }
+
+    #[test]
+    fn keeps_urls_mentions_and_graphemes_whole() {
+        let text =
+            "see https://example.com/a/very/long/path/that/goes/on/and/on @a_long_username_1 ok";
+        let chunks = split_thread(text, &small());
+        assert_eq!(
+            chunks,
+            vec![
+                "see https://example.com/a/very/long/path/that/goes/on/and/on",
+                "@a_long_username_1 ok",
+            ]
+        );
+
+        // 41 family emoji, each two weighted characters, no spaces.
+        let family = "👨‍👩‍👧‍👦";
+        let chunks = split_thread(&family.repeat(41), &small());
+        assert_eq!(
+            chunks,
+            vec![family.repeat(20), family.repeat(20), family.to_string()]
+        );
+    }
+
+    #[test]
+    fn numbers_tweets_within_the_limit() {
+        let config = small();
+        let text = "word ".repeat(100);
+        let chunks = split_thread_numbered(&text, &config);
+        assert_eq!(chunks.len(), 15);
+        assert_eq!(chunks[0], "word word word word word word word 1/15");
+        assert!(chunks[14].ends_with(" 15/15"));
+        for chunk in &chunks {
+            assert!(parse_tweet(chunk, &config).valid, "{}", chunk);
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use super::extract;
+use std::ops::Range;
+use unicode_normalization::UnicodeNormalization;
+use unicode_properties::UnicodeEmoji;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Code points that may not appear in a tweet.
+const INVALID_CHARS: [char; 3] = ['\u{FFFE}', '\u{FEFF}', '\u{FFFF}'];
+
+/// Weight of the code points `start..=end`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct WeightedRange {
+    pub start: u32,
+    pub end: u32,
+    pub weight: usize,
+}
+
+/// How tweet text is counted, in the format of the twitter-text
+/// configuration files.
+///
+/// Weights are multiplied by `scale`: with the default scale of 100, a
+/// weight of 200 counts a character twice.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    pub max_weighted_tweet_length: usize,
+    pub scale: usize,
+    /// Weight of code points outside `ranges`, and of emoji.
+    pub default_weight: usize,
+    /// Length every URL counts as, since all links are shortened to t.co.
+    #[serde(rename = "transformedURLLength")]
+    pub transformed_url_length: usize,
+    pub ranges: Vec<WeightedRange>,
+    /// Count each emoji sequence once with `default_weight`, instead of
+    /// weighting each code point.
+    #[serde(default)]
+    pub emoji_parsing_enabled: bool,
+}
+
+impl Config {
+    /// The original 140 character limit, counting every code point once.
+    pub fn v1() -> Config {
+        Config {
+            max_weighted_tweet_length: 140,
+            scale: 1,
+            default_weight: 1,
+            transformed_url_length: 23,
+            ranges: Vec::new(),
+            emoji_parsing_enabled: false,
+        }
+    }
+
+    /// The 280 character limit, with emoji weighted per code point.
+    pub fn v2() -> Config {
+        Config {
+            max_weighted_tweet_length: 280,
+            scale: 100,
+            default_weight: 200,
+            transformed_url_length: 23,
+            ranges: vec![
+                WeightedRange {
+                    start: 0,
+                    end: 4351,
+                    weight: 100,
+                },
+                WeightedRange {
+                    start: 8192,
+                    end: 8205,
+                    weight: 100,
+                },
+                WeightedRange {
+                    start: 8208,
+                    end: 8223,
+                    weight: 100,
+                },
+                WeightedRange {
+                    start: 8242,
+                    end: 8247,
+                    weight: 100,
+                },
+            ],
+            emoji_parsing_enabled: false,
+        }
+    }
+
+    /// The current limit: as [`Config::v2`], but every emoji counts as two.
+    pub fn v3() -> Config {
+        Config {
+            emoji_parsing_enabled: true,
+            ..Config::v2()
+        }
+    }
+
+    fn weight(&self, c: char) -> usize {
+        let c = c as u32;
+        self.ranges
+            .iter()
+            .find(|range| range.start <= c && c <= range.end)
+            .map_or(self.default_weight, |range| range.weight)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config::v3()
+    }
+}
+
+/// The weighted length of a text, as reported by twitter-text.
+///
+/// Ranges are in UTF-16 code units of the original text, like the counters
+/// of Twitter's web and mobile clients.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseResults {
+    pub weighted_length: usize,
+    /// How much of the limit is used, in thousandths.
+    pub permillage: usize,
+    pub valid: bool,
+    /// The whole text.
+    pub display_range: Range<usize>,
+    /// The part of the text within the limit, up to the first invalid
+    /// character.
+    pub valid_range: Range<usize>,
+}
+
+/// Counts `text` as Twitter does.
+///
+/// Text is NFC normalized first, URLs count as `transformed_url_length`
+/// however long they are, and every other code point, or emoji with
+/// `emoji_parsing_enabled`, counts as its weight.
+pub fn parse_tweet(text: &str, config: &Config) -> ParseResults {
+    let normalized: String = text.nfc().collect();
+    let mut urls = extract::url_ranges(&normalized).into_iter().peekable();
+    let max = config.max_weighted_tweet_length * config.scale;
+
+    let mut weighted = 0;
+    let mut invalid = false;
+    // UTF-16 offsets into the normalized text.
+    let (mut offset, mut valid_offset) = (0, 0);
+    let mut position = 0;
+    while position < normalized.len() {
+        let rest = &normalized[position..];
+        let emoji = if config.emoji_parsing_enabled {
+            emoji_len(rest)
+        } else {
+            None
+        };
+        let (weight, len) = match urls.peek() {
+            Some(url) if url.start == position => {
+                let len = url.len();
+                urls.next();
+                (config.transformed_url_length * config.scale, len)
+            }
+            _ => match emoji {
+                Some(len) => (config.default_weight, len),
+                None => {
+                    let c = rest.chars().next().unwrap();
+                    (config.weight(c), c.len_utf8())
+                }
+            },
+        };
+        let counted = &rest[..len];
+        position += len;
+        weighted += weight;
+        offset += counted.encode_utf16().count();
+        invalid = invalid || counted.contains(&INVALID_CHARS[..]);
+        if !invalid && weighted <= max {
+            valid_offset = offset;
+        }
+    }
+
+    // Normalization may change the length, the ranges refer to the original.
+    let shift = text.encode_utf16().count() as isize - offset as isize;
+    let original = |offset: usize| (offset as isize + shift).max(0) as usize;
+    let weighted_length = weighted / config.scale;
+    ParseResults {
+        weighted_length,
+        permillage: weighted_length * 1000 / config.max_weighted_tweet_length,
+        valid: !invalid && !text.is_empty() && weighted_length <= config.max_weighted_tweet_length,
+        display_range: 0..original(offset),
+        valid_range: 0..original(valid_offset),
+    }
+}
+
+/// Byte length of the emoji sequence `text` starts with, if any.
+fn emoji_len(text: &str) -> Option<usize> {
+    let cluster = text.graphemes(true).next()?;
+    let first = cluster.chars().next()?;
+    if !first.is_emoji_char() || cluster.contains('\u{FE0E}') {
+        return None;
+    }
+    // Digits, `#`, `*`, `©` and `®` are only emoji when asked to be.
+    if (first as u32) < 0x2000 && !cluster.contains(&['\u{FE0F}', '\u{20E3}'][..]) {
+        return None;
+    }
+    Some(cluster.len())
+}
+
+/// Whether a text can be posted as a tweet.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Validation {
+    Valid,
+    /// Nothing but whitespace.
+    Empty,
+    /// Over the length limit; `overflow` is the byte range of the text past
+    /// the limit, for highlighting.
+    TooLong {
+        weighted_length: usize,
+        overflow: Range<usize>,
+    },
+    /// Contains a code point Twitter rejects, at byte offset `position`.
+    InvalidCharacters {
+        position: usize,
+    },
+}
+
+/// Checks whether `text` can be posted, see [`parse_tweet`].
+pub fn validate_tweet(text: &str, config: &Config) -> Validation {
+    if text.trim().is_empty() {
+        return Validation::Empty;
+    }
+    if let Some(position) = text.find(&INVALID_CHARS[..]) {
+        return Validation::InvalidCharacters { position };
+    }
+    let results = parse_tweet(text, config);
+    if results.valid {
+        return Validation::Valid;
+    }
+
+    let mut units = 0;
+    let start = text
+        .char_indices()
+        .find(|&(_, c)| {
+            units += c.len_utf16();
+            units > results.valid_range.end
+        })
+        .map_or(text.len(), |(start, _)| start);
+    Validation::TooLong {
+        weighted_length: results.weighted_length,
+        overflow: start..text.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_yaml::Value;
+    use std::fs;
+    use std::path::Path;
+
+    /// The cases of a section of the twitter-text conformance suite's
+    /// validate.yml.
+    ///
+    /// Its `usernames`, `lists`, `hashtags`, `urls` and
+    /// `urls_without_protocol` sections check that a text is exactly one
+    /// entity, which this crate has no API for; extract.yml covers finding
+    /// those entities.
+    fn cases(section: &str) -> Vec<Value> {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/twitter-text/validate.yml");
+        let suite: Value = serde_yaml::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        suite["tests"][section].as_sequence().unwrap().clone()
+    }
+
+    /// Runs a section of validate.yml expecting [`ParseResults`].
+    fn conformance(section: &str, config: &Config) {
+        let cases = cases(section);
+        assert!(!cases.is_empty());
+        for case in &cases {
+            let text = case["text"].as_str().unwrap();
+            let expected = &case["expected"];
+            let number = |key: &str| expected[key].as_u64().unwrap() as usize;
+            let results = parse_tweet(text, config);
+            assert_eq!(
+                results,
+                ParseResults {
+                    weighted_length: number("weightedLength"),
+                    permillage: number("permillage"),
+                    valid: expected["valid"].as_bool().unwrap(),
+                    display_range: number("displayRangeStart")..number("displayRangeEnd") + 1,
+                    valid_range: number("validRangeStart")..number("validRangeEnd") + 1,
+                },
+                "{}",
+                case["description"].as_str().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn conformance_tweets() {
+        // These cases predate weighted counting and assume the 140 limit.
+        let cases = cases("tweets");
+        assert!(!cases.is_empty());
+        for case in &cases {
+            let validation = validate_tweet(case["text"].as_str().unwrap(), &Config::v1());
+            assert_eq!(
+                validation == Validation::Valid,
+                case["expected"].as_bool().unwrap(),
+                "{}: {:?}",
+                case["description"].as_str().unwrap(),
+                validation
+            );
+        }
+    }
+
+    #[test]
+    fn conformance_v2() {
+        conformance("WeightedTweetsCounterTest", &Config::v2());
+    }
+
+    #[test]
+    fn conformance_v3() {
+        conformance(
+            "WeightedTweetsWithDiscountedEmojiCounterTest",
+            &Config::v3(),
+        );
+        conformance("UnicodeDirectionalMarkerCounterTest", &Config::v3());
+    }
+
+    #[test]
+    fn config_from_json() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "version": 3,
+                "maxWeightedTweetLength": 280,
+                "scale": 100,
+                "defaultWeight": 200,
+                "emojiParsingEnabled": true,
+                "transformedURLLength": 23,
+                "ranges": [
+                    {"start": 0, "end": 4351, "weight": 100},
+                    {"start": 8192, "end": 8205, "weight": 100},
+                    {"start": 8208, "end": 8223, "weight": 100},
+                    {"start": 8242, "end": 8247, "weight": 100}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn validates_tweets() {
+        let config = Config::default();
+        assert_eq!(validate_tweet("hello", &config), Validation::Valid);
+        assert_eq!(validate_tweet(" \n", &config), Validation::Empty);
+        assert_eq!(
+            validate_tweet("ab\u{FFFF}", &config),
+            Validation::InvalidCharacters { position: 2 }
+        );
+
+        // 140 CJK characters weigh 280 and fill the tweet, so the "é!" after
+        // them is the overflow.
+        let text = format!("{}é!", "字".repeat(140));
+        assert_eq!(
+            validate_tweet(&text, &config),
+            Validation::TooLong {
+                weighted_length: 282,
+                overflow: 420..text.len(),
+            }
+        );
+        assert_eq!(&text[420..], "é!");
+    }
+}
@@ -0,0 +1,615 @@
+//! Reading the home, user and mentions timelines.
+//!
+//! Timelines are returned newest first, a page at a time. [`Paginator`]
+//! walks them in either direction by tweet ID, and
+//! [`Twitter::sync_timeline`] catches up on everything new since the last
+//! poll.
+
+use crate::api::Tweet;
+use crate::http::{self, Request};
+use crate::{Result, Twitter};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::sync::Mutex;
+
+/// Whether to get tweet text truncated to 140 characters, the default, or
+/// in full.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TweetMode {
+    Compat,
+    Extended,
+}
+
+/// Statuses/home_timeline request
+///
+/// # Resource Information
+///
+/// Response formats: **JSON**
+///
+/// Requires authentication?: **Yes (user context only)**
+///
+/// Rate limited?: **Yes**
+///
+/// Requests / 15-min window (user auth): **15**
+///
+/// [Docs on Twitter](https://developer.twitter.com/en/docs/tweets/timelines/api-reference/get-statuses-home_timeline)
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct HomeTimelineRequest {
+    /// Number of tweets to try to get, up to 200. Replies and retweets are
+    /// removed after counting, so fewer may be returned.
+    pub count: Option<u8>,
+
+    /// Only tweets with a greater ID, that is newer than this one.
+    pub since_id: Option<u64>,
+
+    /// Only tweets with an ID less than or equal to this one.
+    pub max_id: Option<u64>,
+
+    /// Only include the author's ID in each tweet's `user`.
+    pub trim_user: Option<bool>,
+
+    /// Leave out replies.
+    pub exclude_replies: Option<bool>,
+
+    pub tweet_mode: Option<TweetMode>,
+}
+
+/// Statuses/user_timeline request
+///
+/// # Resource Information
+///
+/// Response formats: **JSON**
+///
+/// Requires authentication?: **Yes**
+///
+/// Rate limited?: **Yes**
+///
+/// Requests / 15-min window (user auth): **900**
+///
+/// Requests / 15-min window (app auth): **1500**
+///
+/// [Docs on Twitter](https://developer.twitter.com/en/docs/tweets/timelines/api-reference/get-statuses-user_timeline)
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct UserTimelineRequest {
+    /// The user whose tweets to get. Either this or `screen_name` is
+    /// required, except for the authenticating user's own timeline.
+    pub user_id: Option<u64>,
+
+    pub screen_name: Option<String>,
+
+    /// Number of tweets to try to get, up to 200. Replies and retweets are
+    /// removed after counting, so fewer may be returned.
+    pub count: Option<u8>,
+
+    /// Only tweets with a greater ID, that is newer than this one.
+    pub since_id: Option<u64>,
+
+    /// Only tweets with an ID less than or equal to this one.
+    pub max_id: Option<u64>,
+
+    /// Only include the author's ID in each tweet's `user`.
+    pub trim_user: Option<bool>,
+
+    /// Leave out replies.
+    pub exclude_replies: Option<bool>,
+
+    /// Set to `false` to leave out retweets.
+    pub include_rts: Option<bool>,
+
+    pub tweet_mode: Option<TweetMode>,
+}
+
+impl UserTimelineRequest {
+    /// The timeline of the user `screen_name`.
+    pub fn screen_name<S: Into<String>>(screen_name: S) -> UserTimelineRequest {
+        UserTimelineRequest {
+            screen_name: Some(screen_name.into()),
+            ..UserTimelineRequest::default()
+        }
+    }
+
+    /// The timeline of the user `user_id`.
+    pub fn user_id(user_id: u64) -> UserTimelineRequest {
+        UserTimelineRequest {
+            user_id: Some(user_id),
+            ..UserTimelineRequest::default()
+        }
+    }
+}
+
+/// Statuses/mentions_timeline request
+///
+/// # Resource Information
+///
+/// Response formats: **JSON**
+///
+/// Requires authentication?: **Yes (user context only)**
+///
+/// Rate limited?: **Yes**
+///
+/// Requests / 15-min window (user auth): **75**
+///
+/// [Docs on Twitter](https://developer.twitter.com/en/docs/tweets/timelines/api-reference/get-statuses-mentions_timeline)
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct MentionsTimelineRequest {
+    /// Number of tweets to try to get, up to 200.
+    pub count: Option<u8>,
+
+    /// Only tweets with a greater ID, that is newer than this one.
+    pub since_id: Option<u64>,
+
+    /// Only tweets with an ID less than or equal to this one.
+    pub max_id: Option<u64>,
+
+    /// Only include the author's ID in each tweet's `user`.
+    pub trim_user: Option<bool>,
+
+    pub tweet_mode: Option<TweetMode>,
+}
+
+/// A request for a page of a timeline, paged by tweet ID.
+pub trait TimelineRequest: Clone + Serialize {
+    /// The endpoint, like `statuses/home_timeline`.
+    fn path(&self) -> &'static str;
+
+    fn since_id(&mut self) -> &mut Option<u64>;
+
+    fn max_id(&mut self) -> &mut Option<u64>;
+
+    /// Removes the request's filters, returning them.
+    fn take_filters(&mut self) -> Filters;
+}
+
+/// The tweets a timeline request leaves out.
+///
+/// Twitter removes them after counting a page, so a page can be empty with
+/// more tweets before it; [`Paginator`] removes them itself instead.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Filters {
+    pub exclude_replies: bool,
+    pub exclude_retweets: bool,
+}
+
+impl Filters {
+    /// Whether `tweet` is left in.
+    pub fn keeps(&self, tweet: &Tweet) -> bool {
+        !(self.exclude_replies && tweet.in_reply_to_user_id.is_some()
+            || self.exclude_retweets && tweet.retweeted_status.is_some())
+    }
+}
+
+macro_rules! timeline_request {
+    ($request:ty, $path:expr, |$this:ident| $filters:expr) => {
+        impl TimelineRequest for $request {
+            fn path(&self) -> &'static str {
+                $path
+            }
+
+            fn since_id(&mut self) -> &mut Option<u64> {
+                &mut self.since_id
+            }
+
+            fn max_id(&mut self) -> &mut Option<u64> {
+                &mut self.max_id
+            }
+
+            fn take_filters(&mut self) -> Filters {
+                let $this = self;
+                $filters
+            }
+        }
+    };
+}
+
+timeline_request!(HomeTimelineRequest, "statuses/home_timeline", |request| {
+    Filters {
+        exclude_replies: request.exclude_replies.take() == Some(true),
+        exclude_retweets: false,
+    }
+});
+timeline_request!(UserTimelineRequest, "statuses/user_timeline", |request| {
+    Filters {
+        exclude_replies: request.exclude_replies.take() == Some(true),
+        exclude_retweets: request.include_rts.take() == Some(false),
+    }
+});
+timeline_request!(
+    MentionsTimelineRequest,
+    "statuses/mentions_timeline",
+    |_request| Filters::default()
+);
+
+impl Twitter {
+    /// The latest tweets from the authenticating user and the accounts they
+    /// follow.
+    pub fn home_timeline(&self, request: &HomeTimelineRequest) -> Result<Vec<Tweet>> {
+        self.timeline(request)
+    }
+
+    /// The latest tweets from one user.
+    pub fn user_timeline(&self, request: &UserTimelineRequest) -> Result<Vec<Tweet>> {
+        self.timeline(request)
+    }
+
+    /// The latest tweets mentioning the authenticating user.
+    pub fn mentions_timeline(&self, request: &MentionsTimelineRequest) -> Result<Vec<Tweet>> {
+        self.timeline(request)
+    }
+
+    /// A page of any timeline.
+    pub fn timeline<R: TimelineRequest>(&self, request: &R) -> Result<Vec<Tweet>> {
+        self.call(&Request::get(request.path(), http::params(request)))
+    }
+
+    /// Pages through the timeline of `request`, starting around its
+    /// `since_id` and `max_id`.
+    pub fn paginate<R: TimelineRequest>(&self, mut request: R) -> Paginator<'_, R> {
+        Paginator {
+            twitter: self,
+            filters: request.take_filters(),
+            request,
+            oldest: None,
+            newest: None,
+            exhausted: false,
+        }
+    }
+}
+
+/// New tweets from [`Twitter::sync_timeline`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Synced {
+    /// Oldest first.
+    pub tweets: Vec<Tweet>,
+    /// The ID to sync from next time: the newest tweet fetched, or the
+    /// previous `since_id` if there were none.
+    pub since_id: Option<u64>,
+}
+
+/// Where the `since_id` of each synced timeline is kept between polls.
+pub trait StateStore {
+    fn load(&self, timeline: &str) -> io::Result<Option<u64>>;
+
+    fn save(&self, timeline: &str, since_id: u64) -> io::Result<()>;
+}
+
+/// A [`StateStore`] that forgets on restart.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    since_ids: Mutex<HashMap<String, u64>>,
+}
+
+impl StateStore for MemoryStore {
+    fn load(&self, timeline: &str) -> io::Result<Option<u64>> {
+        Ok(self.since_ids.lock().unwrap().get(timeline).cloned())
+    }
+
+    fn save(&self, timeline: &str, since_id: u64) -> io::Result<()> {
+        self.since_ids
+            .lock()
+            .unwrap()
+            .insert(timeline.to_string(), since_id);
+        Ok(())
+    }
+}
+
+impl Twitter {
+    /// Fetches every tweet of `request`'s timeline after `since_id`,
+    /// paging back as far as needed to leave no gap.
+    ///
+    /// Without a `since_id` there is nothing to catch up on, so only the
+    /// latest page is fetched.
+    pub fn sync_timeline<R: TimelineRequest>(
+        &self,
+        request: &R,
+        since_id: Option<u64>,
+    ) -> Result<Synced> {
+        let mut request = request.clone();
+        *request.since_id() = since_id;
+        let mut pages = self.paginate(request);
+        let mut tweets = Vec::new();
+        loop {
+            tweets.extend(pages.older()?);
+            let since_id = match since_id {
+                Some(since_id) => since_id,
+                None => break,
+            };
+            // The next page would be `since_id < id <= oldest - 1`.
+            let reached = pages
+                .oldest_id()
+                .is_some_and(|oldest| oldest.saturating_sub(1) <= since_id);
+            if reached || pages.exhausted() {
+                break;
+            }
+        }
+        tweets.retain(|tweet| since_id.is_none_or(|since_id| tweet.id > since_id));
+        tweets.reverse();
+        Ok(Synced {
+            tweets,
+            since_id: pages.newest_id().or(since_id),
+        })
+    }
+
+    /// As [`Twitter::sync_timeline`], from the `since_id` stored for
+    /// `timeline`.
+    ///
+    /// The new `since_id` is not saved: save it once the tweets are handled,
+    /// so a crash fetches them again rather than losing them.
+    pub fn sync_from_store<R, S>(&self, request: &R, store: &S, timeline: &str) -> Result<Synced>
+    where
+        R: TimelineRequest,
+        S: StateStore + ?Sized,
+    {
+        let since_id = store.load(timeline)?;
+        self.sync_timeline(request, since_id)
+    }
+}
+
+/// Walks a timeline backward with `max_id` and forward with `since_id`.
+///
+/// Every tweet is returned once: pages overlapping what was already seen
+/// are trimmed. The request's [`Filters`] are applied to each page after
+/// it is fetched, so a page may be empty in the middle of the timeline.
+pub struct Paginator<'a, R> {
+    twitter: &'a Twitter,
+    request: R,
+    filters: Filters,
+    oldest: Option<u64>,
+    newest: Option<u64>,
+    exhausted: bool,
+}
+
+impl<'a, R: TimelineRequest> Paginator<'a, R> {
+    /// The page of tweets before the oldest seen, or the first page.
+    ///
+    /// Empty once the start of the timeline, or the request's `since_id`,
+    /// is reached, see [`Paginator::exhausted`].
+    pub fn older(&mut self) -> Result<Vec<Tweet>> {
+        let mut request = self.request.clone();
+        if let Some(oldest) = self.oldest {
+            if oldest == 0 {
+                self.exhausted = true;
+                return Ok(Vec::new());
+            }
+            *request.max_id() = Some(oldest - 1);
+        }
+        let oldest = self.oldest;
+        let page = self.fetch(&request, |id| oldest.is_none_or(|oldest| id < oldest))?;
+        // Nothing older came back: the start of the timeline.
+        self.exhausted = self.oldest == oldest;
+        Ok(page)
+    }
+
+    /// The page of tweets after the newest seen, or the first page.
+    ///
+    /// When more tweets arrived than fit in a page this returns the newest
+    /// of them, leaving a gap to fill with `max_id`.
+    pub fn newer(&mut self) -> Result<Vec<Tweet>> {
+        let mut request = self.request.clone();
+        if let Some(newest) = self.newest {
+            *request.since_id() = Some(newest);
+            *request.max_id() = None;
+        }
+        let newest = self.newest;
+        self.fetch(&request, |id| newest.is_none_or(|newest| id > newest))
+    }
+
+    /// Whether the last call to [`Paginator::older`] found no older tweets,
+    /// filtered or not.
+    pub fn exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    /// ID of the oldest tweet seen so far.
+    pub fn oldest_id(&self) -> Option<u64> {
+        self.oldest
+    }
+
+    /// ID of the newest tweet seen so far.
+    pub fn newest_id(&self) -> Option<u64> {
+        self.newest
+    }
+
+    fn fetch<F>(&mut self, request: &R, unseen: F) -> Result<Vec<Tweet>>
+    where
+        F: Fn(u64) -> bool,
+    {
+        let mut page = self.twitter.timeline(request)?;
+        page.retain(|tweet| unseen(tweet.id));
+        for tweet in &page {
+            self.oldest = Some(self.oldest.map_or(tweet.id, |oldest| oldest.min(tweet.id)));
+            self.newest = Some(self.newest.map_or(tweet.id, |newest| newest.max(tweet.id)));
+        }
+        page.retain(|tweet| self.filters.keeps(tweet));
+        Ok(page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock::Mock;
+    use std::fs;
+    use std::path::Path;
+
+    /// A JSON page of tweets with `ids`.
+    fn page(ids: &[u64]) -> String {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tweets/plain_text.json");
+        let tweet: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        let tweets: Vec<_> = ids
+            .iter()
+            .map(|&id| {
+                let mut tweet = tweet.clone();
+                tweet["id"] = id.into();
+                tweet["id_str"] = id.to_string().into();
+                tweet
+            })
+            .collect();
+        serde_json::Value::from(tweets).to_string()
+    }
+
+    fn ids(tweets: &[Tweet]) -> Vec<u64> {
+        tweets.iter().map(|tweet| tweet.id).collect()
+    }
+
+    fn param<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+        request
+            .query
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn gets_timelines() {
+        let mock = Mock::default();
+        mock.respond(200, &page(&[3, 2])).respond(200, &page(&[1]));
+        let twitter = Twitter::new(mock.clone());
+
+        let request = UserTimelineRequest {
+            count: Some(2),
+            include_rts: Some(false),
+            tweet_mode: Some(TweetMode::Extended),
+            ..UserTimelineRequest::screen_name("TwitterDev")
+        };
+        assert_eq!(ids(&twitter.user_timeline(&request).unwrap()), vec![3, 2]);
+        assert_eq!(
+            ids(&twitter.mentions_timeline(&Default::default()).unwrap()),
+            vec![1]
+        );
+
+        let sent = mock.requests();
+        assert_eq!(
+            sent[0].url,
+            "https://api.twitter.com/1.1/statuses/user_timeline.json"
+        );
+        assert_eq!(param(&sent[0], "screen_name"), Some("TwitterDev"));
+        assert_eq!(param(&sent[0], "include_rts"), Some("false"));
+        assert_eq!(param(&sent[0], "tweet_mode"), Some("extended"));
+        assert!(sent[1].url.ends_with("/statuses/mentions_timeline.json"));
+        assert!(sent[1].query.is_empty());
+    }
+
+    #[test]
+    fn pages_backward_and_forward() {
+        let mock = Mock::default();
+        mock.respond(200, &page(&[30, 20]))
+            // A page repeating the boundary tweet.
+            .respond(200, &page(&[20, 19, 10]))
+            .respond(200, &page(&[]))
+            .respond(200, &page(&[41, 40, 30]));
+        let twitter = Twitter::new(mock.clone());
+
+        let request = HomeTimelineRequest {
+            count: Some(3),
+            ..HomeTimelineRequest::default()
+        };
+        let mut timeline = twitter.paginate(request);
+        assert_eq!(ids(&timeline.older().unwrap()), vec![30, 20]);
+        assert_eq!(ids(&timeline.older().unwrap()), vec![19, 10]);
+        assert!(timeline.older().unwrap().is_empty());
+        assert_eq!(ids(&timeline.newer().unwrap()), vec![41, 40]);
+        assert_eq!(
+            (timeline.oldest_id(), timeline.newest_id()),
+            (Some(10), Some(41))
+        );
+
+        let sent = mock.requests();
+        assert_eq!(param(&sent[0], "max_id"), None);
+        assert_eq!(param(&sent[1], "max_id"), Some("19"));
+        assert_eq!(param(&sent[2], "max_id"), Some("9"));
+        assert_eq!(param(&sent[3], "since_id"), Some("30"));
+        assert_eq!(param(&sent[3], "max_id"), None);
+    }
+
+    #[test]
+    fn syncs_gaps_oldest_first() {
+        let mock = Mock::default();
+        mock.respond(200, &page(&[14, 13]))
+            .respond(200, &page(&[12, 11]))
+            .respond(200, &page(&[]));
+        let twitter = Twitter::new(mock.clone());
+        let store = MemoryStore::default();
+        store.save("mentions", 10).unwrap();
+
+        let request = MentionsTimelineRequest {
+            count: Some(2),
+            ..MentionsTimelineRequest::default()
+        };
+        let synced = twitter
+            .sync_from_store(&request, &store, "mentions")
+            .unwrap();
+        assert_eq!(ids(&synced.tweets), vec![11, 12, 13, 14]);
+        assert_eq!(synced.since_id, Some(14));
+        store.save("mentions", 14).unwrap();
+
+        let synced = twitter
+            .sync_from_store(&request, &store, "mentions")
+            .unwrap();
+        assert!(synced.tweets.is_empty());
+        assert_eq!(synced.since_id, Some(14));
+
+        let sent = mock.requests();
+        let paging: Vec<_> = sent
+            .iter()
+            .map(|request| (param(request, "since_id"), param(request, "max_id")))
+            .collect();
+        assert_eq!(
+            paging,
+            vec![
+                (Some("10"), None),
+                (Some("10"), Some("12")),
+                // No request for `max_id=10`, which cannot be after 10.
+                (Some("14"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn syncs_past_filtered_pages() {
+        let mut replies: serde_json::Value = serde_json::from_str(&page(&[14, 13])).unwrap();
+        for reply in replies.as_array_mut().unwrap() {
+            reply["in_reply_to_user_id"] = 2244994945u64.into();
+        }
+        let mock = Mock::default();
+        mock.respond(200, &page(&[15]))
+            .respond(200, &replies.to_string())
+            .respond(200, &page(&[12, 11]))
+            .respond(200, &page(&[]));
+        let twitter = Twitter::new(mock.clone());
+
+        let request = UserTimelineRequest {
+            count: Some(2),
+            exclude_replies: Some(true),
+            ..UserTimelineRequest::screen_name("TwitterDev")
+        };
+        let synced = twitter.sync_timeline(&request, Some(5)).unwrap();
+        assert_eq!(ids(&synced.tweets), vec![11, 12, 15]);
+        assert_eq!(synced.since_id, Some(15));
+
+        let sent = mock.requests();
+        assert_eq!(sent.len(), 4);
+        assert_eq!(param(&sent[2], "max_id"), Some("12"));
+        assert_eq!(param(&sent[3], "max_id"), Some("10"));
+        // Replies are left out here rather than by Twitter.
+        assert!(sent
+            .iter()
+            .all(|request| param(request, "exclude_replies").is_none()));
+    }
+
+    #[test]
+    fn first_sync_fetches_one_page() {
+        let mock = Mock::default();
+        mock.respond(200, &page(&[5, 4]));
+        let twitter = Twitter::new(mock);
+
+        let synced = twitter
+            .sync_timeline(&HomeTimelineRequest::default(), None)
+            .unwrap();
+        assert_eq!(ids(&synced.tweets), vec![4, 5]);
+        assert_eq!(synced.since_id, Some(5));
+    }
+}
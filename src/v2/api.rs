@@ -0,0 +1,696 @@
+//! Models of the v2 API.
+//!
+//! Objects only carry their ID and a few defaults unless more fields are
+//! asked for with [`Fields`]. Objects they refer to, like a tweet's author,
+//! are left out of them too: [`Expansion`]s add those to the response's
+//! [`Includes`].
+
+use crate::api::Coordinate;
+use crate::http::{self, Body, Method, Request};
+use crate::{Result, Twitter};
+#[cfg(feature = "extra-fields")]
+use serde_json::Map;
+use serde_json::Value;
+
+/// A v2 tweet.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Tweet {
+    pub id: String,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_id: Option<String>,
+    /// Like `2019-06-04T23:12:08.000Z`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    /// The tweet the thread this one belongs to started with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_reply_to_user_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub possibly_sensitive: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The tweets this one quotes, replies to or retweets.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub referenced_tweets: Vec<ReferencedTweet>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Attachments>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geo: Option<TweetGeo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_metrics: Option<TweetMetrics>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entities: Option<Entities>,
+    /// What Twitter found the tweet to be about.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_annotations: Vec<ContextAnnotation>,
+    /// `everyone`, `mentionedUsers` or `following`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_settings: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withheld: Option<Withheld>,
+    /// Fields Twitter sent that are not modelled above, as on every model.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ReferencedTweet {
+    #[serde(rename = "type")]
+    pub kind: ReferenceKind,
+    pub id: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceKind {
+    Quoted,
+    RepliedTo,
+    Retweeted,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Attachments {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub media_keys: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub poll_ids: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct TweetGeo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub place_id: Option<String>,
+    /// Only when the author shared their exact location.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coordinates: Option<Coordinate>,
+}
+
+/// Parts of a text, with their `start` and `end` in characters.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Entities {
+    /// Only on tweets.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cashtags: Vec<Tag>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hashtags: Vec<Tag>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mentions: Vec<Mention>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub urls: Vec<Url>,
+}
+
+/// A named thing Twitter recognized in a text.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Annotation {
+    pub start: usize,
+    pub end: usize,
+    pub probability: f64,
+    /// Like `Person`, `Place` or `Product`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub normalized_text: String,
+}
+
+/// A hashtag or cashtag, without `#` or `$`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Tag {
+    pub start: usize,
+    pub end: usize,
+    pub tag: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Mention {
+    pub start: usize,
+    pub end: usize,
+    /// Without `@`.
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Url {
+    pub start: usize,
+    pub end: usize,
+    /// The t.co link.
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expanded_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_url: Option<String>,
+    /// Where the link ends up after redirects.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unwound_url: Option<String>,
+    /// The HTTP status of the page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// A topic a tweet is about, within its domain.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ContextAnnotation {
+    /// Like `Brand` or `Sports Event`.
+    pub domain: ContextEntity,
+    pub entity: ContextEntity,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ContextEntity {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Where a tweet or user is withheld.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Withheld {
+    /// Whether a tweet is withheld over a copyright claim.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copyright: Option<bool>,
+    /// Two-letter codes, or `XX` and `XY` for all countries.
+    #[serde(default)]
+    pub country_codes: Vec<String>,
+    /// `tweet` or `user`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TweetMetrics {
+    pub retweet_count: u64,
+    pub reply_count: u64,
+    pub like_count: u64,
+    pub quote_count: u64,
+}
+
+/// A v2 user.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct User {
+    pub id: String,
+    pub name: String,
+    /// The handle, without `@`.
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned_tweet_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_image_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protected: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_metrics: Option<UserMetrics>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entities: Option<UserEntities>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withheld: Option<Withheld>,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The links in a user's profile URL and the parts of their description.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct UserEntities {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<Entities>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<Entities>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct UserMetrics {
+    pub followers_count: u64,
+    pub following_count: u64,
+    pub tweet_count: u64,
+    pub listed_count: u64,
+}
+
+/// A photo, GIF or video attached to a tweet.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Media {
+    /// Identifies the media within v2 responses; not a media ID.
+    pub media_key: String,
+    #[serde(rename = "type")]
+    pub kind: MediaKind,
+    /// Only for photos.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Only for GIFs and videos.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview_image_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alt_text: Option<String>,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    Photo,
+    AnimatedGif,
+    Video,
+}
+
+/// A named location tagged on a tweet.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Place {
+    pub id: String,
+    /// Like `Manhattan, NY`.
+    pub full_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country_code: Option<String>,
+    /// Like `city` or `poi`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub place_type: Option<String>,
+    /// The place's bounds, as GeoJSON.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geo: Option<Value>,
+    /// The IDs of the places this one is in.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contained_within: Vec<String>,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Poll {
+    pub id: String,
+    pub options: Vec<PollOption>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_minutes: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_datetime: Option<String>,
+    /// `open` or `closed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voting_status: Option<String>,
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PollOption {
+    /// From 1.
+    pub position: u32,
+    pub label: String,
+    pub votes: u64,
+}
+
+/// Something wrong with part of a request, which did not fail as a whole.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Problem {
+    /// Like `Invalid Rule`.
+    pub title: String,
+    /// A URL naming the kind of problem.
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// The value at fault, like the text of a rule.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// The ID at fault, like that of a duplicate rule.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<String>,
+    /// The kind of object not found, like `user`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_id: Option<String>,
+}
+
+/// Tweet fields beyond `id` and `text` to return.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TweetField {
+    Attachments,
+    AuthorId,
+    ContextAnnotations,
+    ConversationId,
+    CreatedAt,
+    Entities,
+    Geo,
+    Id,
+    InReplyToUserId,
+    Lang,
+    PossiblySensitive,
+    PublicMetrics,
+    ReferencedTweets,
+    ReplySettings,
+    Source,
+    Text,
+    Withheld,
+}
+
+/// User fields beyond `id`, `name` and `username` to return.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserField {
+    CreatedAt,
+    Description,
+    Entities,
+    Id,
+    Location,
+    Name,
+    PinnedTweetId,
+    ProfileImageUrl,
+    Protected,
+    PublicMetrics,
+    Url,
+    Username,
+    Verified,
+    Withheld,
+}
+
+/// Media fields beyond `media_key` and `type` to return.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaField {
+    AltText,
+    DurationMs,
+    Height,
+    MediaKey,
+    PreviewImageUrl,
+    Type,
+    Url,
+    Width,
+}
+
+/// Poll fields beyond `id` and `options` to return.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PollField {
+    DurationMinutes,
+    EndDatetime,
+    Id,
+    Options,
+    VotingStatus,
+}
+
+/// Place fields beyond `id` and `full_name` to return.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaceField {
+    ContainedWithin,
+    Country,
+    CountryCode,
+    FullName,
+    Geo,
+    Id,
+    Name,
+    PlaceType,
+}
+
+/// Objects referred to by the tweets returned, to add to [`Includes`].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum Expansion {
+    #[serde(rename = "author_id")]
+    AuthorId,
+    #[serde(rename = "referenced_tweets.id")]
+    ReferencedTweets,
+    /// The authors of the referenced tweets.
+    #[serde(rename = "referenced_tweets.id.author_id")]
+    ReferencedTweetsAuthorId,
+    #[serde(rename = "in_reply_to_user_id")]
+    InReplyToUserId,
+    #[serde(rename = "attachments.media_keys")]
+    Media,
+    #[serde(rename = "attachments.poll_ids")]
+    Polls,
+    #[serde(rename = "geo.place_id")]
+    Place,
+    #[serde(rename = "entities.mentions.username")]
+    Mentions,
+}
+
+/// The fields and expansions a v2 request returns.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Fields {
+    pub expansions: Option<Vec<Expansion>>,
+    #[serde(rename = "tweet.fields")]
+    pub tweet: Option<Vec<TweetField>>,
+    #[serde(rename = "user.fields")]
+    pub user: Option<Vec<UserField>>,
+    #[serde(rename = "media.fields")]
+    pub media: Option<Vec<MediaField>>,
+    #[serde(rename = "poll.fields")]
+    pub poll: Option<Vec<PollField>>,
+    #[serde(rename = "place.fields")]
+    pub place: Option<Vec<PlaceField>>,
+}
+
+/// A v2 response, with the objects its `data` refers to.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Response<T> {
+    /// Missing when nothing was found.
+    pub data: Option<T>,
+    #[serde(default)]
+    pub includes: Includes,
+    /// Objects that could not be returned, like deleted tweets.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<Problem>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// The objects added by [`Expansion`]s.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Includes {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tweets: Vec<Tweet>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<User>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub media: Vec<Media>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub places: Vec<Place>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub polls: Vec<Poll>,
+}
+
+/// About a page of results.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Meta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub newest_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oldest_id: Option<String>,
+    /// Pass as `pagination_token` to get the next page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_token: Option<String>,
+}
+
+/// Tweets lookup request
+///
+/// # Resource Information
+///
+/// Response formats: **JSON**
+///
+/// Requires authentication?: **Yes**
+///
+/// Rate limited?: **Yes**
+///
+/// Requests / 15-min window (user auth): **900**
+///
+/// Requests / 15-min window (app auth): **300**
+///
+/// [Docs on Twitter](https://developer.twitter.com/en/docs/twitter-api/tweets/lookup/api-reference/get-tweets)
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct TweetsRequest {
+    /// Up to 100 tweet IDs.
+    pub ids: Vec<String>,
+    #[serde(flatten)]
+    pub fields: Fields,
+}
+
+impl Twitter {
+    /// Looks up tweets by ID. Missing tweets are reported in
+    /// [`Response::errors`].
+    pub fn lookup_tweets(&self, request: &TweetsRequest) -> Result<Response<Vec<Tweet>>> {
+        self.call(&Request::v2(
+            Method::Get,
+            "tweets",
+            http::params(request),
+            Body::Empty,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock::Mock;
+
+    #[test]
+    fn fields_serialize_to_params() {
+        let request = TweetsRequest {
+            ids: vec!["1".to_string(), "2".to_string()],
+            fields: Fields {
+                expansions: Some(vec![
+                    Expansion::AuthorId,
+                    Expansion::ReferencedTweets,
+                    Expansion::Media,
+                ]),
+                tweet: Some(vec![TweetField::CreatedAt, TweetField::PublicMetrics]),
+                user: Some(vec![UserField::ProfileImageUrl]),
+                media: Some(vec![MediaField::AltText, MediaField::Url]),
+                ..Fields::default()
+            },
+        };
+        let mut params = http::params(&request);
+        params.sort();
+        let expected = [
+            (
+                "expansions",
+                "author_id,referenced_tweets.id,attachments.media_keys",
+            ),
+            ("ids", "1,2"),
+            ("media.fields", "alt_text,url"),
+            ("tweet.fields", "created_at,public_metrics"),
+            ("user.fields", "profile_image_url"),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        assert_eq!(params, expected);
+    }
+
+    #[test]
+    fn parses_responses_with_includes() {
+        let mock = Mock::default();
+        mock.respond(200, include_str!("../../tests/fixtures/v2/tweets.json"));
+        let twitter = Twitter::new(mock.clone());
+
+        let request = TweetsRequest {
+            ids: vec![
+                "1261326399320715264".to_string(),
+                "1278347468690915330".to_string(),
+                "404".to_string(),
+            ],
+            ..TweetsRequest::default()
+        };
+        let response = twitter.lookup_tweets(&request).unwrap();
+        let tweets = response.data.unwrap();
+        assert_eq!(tweets.len(), 2);
+        assert_eq!(tweets[0].author_id.as_deref(), Some("2244994945"));
+        assert_eq!(
+            tweets[0].referenced_tweets,
+            [ReferencedTweet {
+                kind: ReferenceKind::Quoted,
+                id: "1260294888811347969".to_string(),
+            }]
+        );
+        assert_eq!(tweets[0].public_metrics.as_ref().unwrap().like_count, 84);
+        assert_eq!(
+            tweets[1].attachments.as_ref().unwrap().media_keys,
+            ["3_1278347466119368705"]
+        );
+        let urls = &tweets[0].entities.as_ref().unwrap().urls;
+        assert_eq!((urls[0].start, urls[0].end), (32, 55));
+        let annotation = &tweets[0].context_annotations[0];
+        assert_eq!(
+            (
+                annotation.domain.name.as_str(),
+                annotation.entity.name.as_str()
+            ),
+            ("Brand", "Twitter")
+        );
+        assert_eq!(tweets[0].reply_settings.as_deref(), Some("everyone"));
+        let annotations = &tweets[1].entities.as_ref().unwrap().annotations;
+        assert_eq!(annotations[0].normalized_text, "Twitter API");
+        assert_eq!(tweets[1].withheld.as_ref().unwrap().country_codes, ["DE"]);
+
+        let includes = &response.includes;
+        assert_eq!(includes.users[0].username, "TwitterDev");
+        let user_entities = includes.users[0].entities.as_ref().unwrap();
+        assert_eq!(
+            user_entities.description.as_ref().unwrap().hashtags[0].tag,
+            "TwitterDev"
+        );
+        assert_eq!(
+            user_entities.url.as_ref().unwrap().urls[0]
+                .expanded_url
+                .as_deref(),
+            Some("https://developer.twitter.com/en/community")
+        );
+        assert_eq!(includes.media[0].kind, MediaKind::Photo);
+        assert_eq!(includes.polls[0].options[1].votes, 4);
+        assert_eq!(includes.places[0].full_name, "Manhattan, NY");
+        assert_eq!(includes.places[0].contained_within, ["96683cc9126741d1"]);
+        assert_eq!(includes.tweets[0].id, "1260294888811347969");
+
+        assert_eq!(response.errors[0].resource_id.as_deref(), Some("404"));
+        assert_eq!(response.errors[0].title, "Not Found Error");
+
+        let sent = &mock.requests()[0];
+        assert_eq!(sent.url, "https://api.twitter.com/2/tweets");
+        assert_eq!(
+            sent.query,
+            [(
+                "ids".to_string(),
+                "1261326399320715264,1278347468690915330,404".to_string()
+            )]
+        );
+    }
+
+    #[cfg(feature = "extra-fields")]
+    #[test]
+    fn keeps_unknown_fields_of_includes() {
+        let mut response: Value =
+            serde_json::from_str(include_str!("../../tests/fixtures/v2/tweets.json")).unwrap();
+        for kind in &["media", "places", "polls"] {
+            response["includes"][kind][0]["new_field"] = Value::from(*kind);
+        }
+        let response: Response<Vec<Tweet>> = serde_json::from_value(response).unwrap();
+        let includes = &response.includes;
+        assert_eq!(includes.media[0].extra["new_field"], "media");
+        assert_eq!(includes.places[0].extra["new_field"], "places");
+        assert_eq!(includes.polls[0].extra["new_field"], "polls");
+        let round_tripped = serde_json::to_value(&includes.media[0]).unwrap();
+        assert_eq!(round_tripped["new_field"], "media");
+    }
+}
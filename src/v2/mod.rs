@@ -0,0 +1,9 @@
+//! The v2 API.
+//!
+//! v2 sends IDs as strings and reports problems with parts of a request,
+//! like one invalid rule of several, as [`Problem`](api::Problem)s
+//! alongside the data.
+
+pub mod api;
+pub mod resolve;
+pub mod stream;
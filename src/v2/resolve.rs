@@ -0,0 +1,307 @@
+//! Putting the objects of a response's `includes` back on its tweets.
+//!
+//! A [`Resolver`] indexes a response's tweets and includes by ID, and
+//! [`Resolver::resolve`] gives a tweet its author, attachments, place and
+//! the tweets it refers to as [`Resolved`]. IDs it cannot find are listed
+//! with the [`Problem`] Twitter reported about them, if any.
+
+use super::api::{Includes, Media, Place, Poll, Problem, ReferenceKind, Response, Tweet, User};
+use super::stream::StreamedTweet;
+use std::collections::HashMap;
+
+/// The kinds of objects a tweet refers to by ID.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ObjectKind {
+    Tweet,
+    User,
+    Media,
+    Poll,
+    Place,
+}
+
+impl ObjectKind {
+    /// As in [`Problem::resource_type`].
+    fn resource_type(self) -> &'static str {
+        match self {
+            ObjectKind::Tweet => "tweet",
+            ObjectKind::User => "user",
+            ObjectKind::Media => "media",
+            ObjectKind::Poll => "poll",
+            ObjectKind::Place => "place",
+        }
+    }
+}
+
+/// An object a tweet refers to that is not in the response.
+///
+/// Either Twitter could not return it, like a tweet since deleted, and
+/// usually says why in `problem`, or its expansion was not asked for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unresolved<'a> {
+    pub kind: ObjectKind,
+    pub id: &'a str,
+    pub problem: Option<&'a Problem>,
+}
+
+/// A tweet with the objects it refers to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Resolved<'a> {
+    pub tweet: &'a Tweet,
+    pub author: Option<&'a User>,
+    pub in_reply_to_user: Option<&'a User>,
+    pub media: Vec<&'a Media>,
+    pub poll: Option<&'a Poll>,
+    pub place: Option<&'a Place>,
+    pub quoted: Option<&'a Tweet>,
+    pub replied_to: Option<&'a Tweet>,
+    pub retweeted: Option<&'a Tweet>,
+    /// What could not be found, in the order above.
+    pub unresolved: Vec<Unresolved<'a>>,
+}
+
+/// The tweets and included objects of a response, by ID.
+#[derive(Clone, Debug, Default)]
+pub struct Resolver<'a> {
+    tweets: HashMap<&'a str, &'a Tweet>,
+    users: HashMap<&'a str, &'a User>,
+    media: HashMap<&'a str, &'a Media>,
+    polls: HashMap<&'a str, &'a Poll>,
+    places: HashMap<&'a str, &'a Place>,
+    errors: &'a [Problem],
+}
+
+impl<'a> Resolver<'a> {
+    /// Indexes `includes` along with the response's own `tweets`, which
+    /// can refer to each other, and its `errors`.
+    pub fn new(tweets: &'a [Tweet], includes: &'a Includes, errors: &'a [Problem]) -> Resolver<'a> {
+        Resolver {
+            tweets: tweets
+                .iter()
+                .chain(&includes.tweets)
+                .map(|tweet| (tweet.id.as_str(), tweet))
+                .collect(),
+            users: includes
+                .users
+                .iter()
+                .map(|user| (user.id.as_str(), user))
+                .collect(),
+            media: includes
+                .media
+                .iter()
+                .map(|media| (media.media_key.as_str(), media))
+                .collect(),
+            polls: includes
+                .polls
+                .iter()
+                .map(|poll| (poll.id.as_str(), poll))
+                .collect(),
+            places: includes
+                .places
+                .iter()
+                .map(|place| (place.id.as_str(), place))
+                .collect(),
+            errors,
+        }
+    }
+
+    /// Finds the objects `tweet` refers to.
+    ///
+    /// Referenced tweets can be resolved in turn, for instance to get the
+    /// author of a quoted tweet.
+    pub fn resolve(&self, tweet: &'a Tweet) -> Resolved<'a> {
+        let mut unresolved = Vec::new();
+        let mut finder = Finder {
+            errors: self.errors,
+            unresolved: &mut unresolved,
+        };
+
+        let author = tweet
+            .author_id
+            .as_deref()
+            .and_then(|id| finder.find(&self.users, ObjectKind::User, id));
+        let in_reply_to_user = tweet
+            .in_reply_to_user_id
+            .as_deref()
+            .and_then(|id| finder.find(&self.users, ObjectKind::User, id));
+        let attachments = tweet.attachments.as_ref();
+        let media_keys = attachments.map_or(&[][..], |attachments| &attachments.media_keys);
+        let media = media_keys
+            .iter()
+            .filter_map(|key| finder.find(&self.media, ObjectKind::Media, key))
+            .collect();
+        let poll_ids = attachments.map_or(&[][..], |attachments| &attachments.poll_ids);
+        let poll = poll_ids
+            .iter()
+            .filter_map(|id| finder.find(&self.polls, ObjectKind::Poll, id))
+            .next();
+        let place = tweet
+            .geo
+            .as_ref()
+            .and_then(|geo| geo.place_id.as_deref())
+            .and_then(|id| finder.find(&self.places, ObjectKind::Place, id));
+        let (mut quoted, mut replied_to, mut retweeted) = (None, None, None);
+        for reference in &tweet.referenced_tweets {
+            let found = finder.find(&self.tweets, ObjectKind::Tweet, &reference.id);
+            match reference.kind {
+                ReferenceKind::Quoted => quoted = found,
+                ReferenceKind::RepliedTo => replied_to = found,
+                ReferenceKind::Retweeted => retweeted = found,
+            }
+        }
+
+        Resolved {
+            tweet,
+            author,
+            in_reply_to_user,
+            media,
+            poll,
+            place,
+            quoted,
+            replied_to,
+            retweeted,
+            unresolved,
+        }
+    }
+}
+
+/// Looks objects up, noting those missing.
+struct Finder<'a, 'b> {
+    errors: &'a [Problem],
+    unresolved: &'b mut Vec<Unresolved<'a>>,
+}
+
+impl<'a, 'b> Finder<'a, 'b> {
+    fn find<T>(
+        &mut self,
+        objects: &HashMap<&'a str, &'a T>,
+        kind: ObjectKind,
+        id: &'a str,
+    ) -> Option<&'a T> {
+        let found = objects.get(id).copied();
+        if found.is_none() {
+            let problem = self.errors.iter().find(|problem| {
+                problem.resource_type.as_deref() == Some(kind.resource_type())
+                    && problem.resource_id.as_deref().or(problem.value.as_deref()) == Some(id)
+            });
+            self.unresolved.push(Unresolved { kind, id, problem });
+        }
+        found
+    }
+}
+
+impl Response<Vec<Tweet>> {
+    /// The tweets of the response with the objects they refer to.
+    pub fn resolve(&self) -> Vec<Resolved<'_>> {
+        let tweets = self.data.as_deref().unwrap_or_default();
+        let resolver = Resolver::new(tweets, &self.includes, &self.errors);
+        tweets.iter().map(|tweet| resolver.resolve(tweet)).collect()
+    }
+}
+
+impl Response<Tweet> {
+    /// The tweet of the response with the objects it refers to.
+    pub fn resolve(&self) -> Option<Resolved<'_>> {
+        let tweet = self.data.as_ref()?;
+        let resolver = Resolver::new(std::slice::from_ref(tweet), &self.includes, &self.errors);
+        Some(resolver.resolve(tweet))
+    }
+}
+
+impl StreamedTweet {
+    /// The tweet with the objects it refers to.
+    pub fn resolve(&self) -> Resolved<'_> {
+        let resolver = Resolver::new(
+            std::slice::from_ref(&self.data),
+            &self.includes,
+            &self.errors,
+        );
+        resolver.resolve(&self.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn resolves_includes() {
+        let mut response: Value =
+            serde_json::from_str(include_str!("../../tests/fixtures/v2/tweets.json")).unwrap();
+        response["data"][1]["in_reply_to_user_id"] = json!("783214");
+        response["data"][1]["referenced_tweets"] =
+            json!([{"type": "replied_to", "id": "1278347460000000000"}]);
+        response["errors"].as_array_mut().unwrap().push(json!({
+            "value": "1278347460000000000",
+            "detail": "Sorry, you are not authorized to see the Tweet with referenced_tweets.id: [1278347460000000000].",
+            "title": "Authorization Error",
+            "resource_type": "tweet",
+            "parameter": "referenced_tweets.id",
+            "resource_id": "1278347460000000000",
+            "type": "https://api.twitter.com/2/problems/not-authorized-for-resource",
+        }));
+        let response: Response<Vec<Tweet>> = serde_json::from_value(response).unwrap();
+
+        let resolved = response.resolve();
+        let first = &resolved[0];
+        assert_eq!(first.author.unwrap().username, "TwitterDev");
+        assert_eq!(first.poll.unwrap().options[0].label, "Conversations");
+        assert_eq!(first.place.unwrap().full_name, "Manhattan, NY");
+        assert!(first.media.is_empty());
+        assert_eq!(first.unresolved, []);
+        let quoted = first.quoted.unwrap();
+        assert_eq!(quoted.id, "1260294888811347969");
+        let resolver = Resolver::new(
+            response.data.as_deref().unwrap(),
+            &response.includes,
+            &response.errors,
+        );
+        assert_eq!(resolver.resolve(quoted).author.unwrap().username, "Twitter");
+
+        let second = &resolved[1];
+        assert_eq!(
+            second.media[0].alt_text.as_deref(),
+            Some("A diagram of the new endpoints")
+        );
+        assert_eq!(second.in_reply_to_user.unwrap().username, "Twitter");
+        assert_eq!(second.replied_to, None);
+        assert_eq!(second.unresolved.len(), 1);
+        let missing = &second.unresolved[0];
+        assert_eq!(
+            (missing.kind, missing.id),
+            (ObjectKind::Tweet, "1278347460000000000")
+        );
+        assert_eq!(missing.problem.unwrap().title, "Authorization Error");
+    }
+
+    #[test]
+    fn reports_missing_expansions() {
+        let streamed: StreamedTweet = serde_json::from_value(json!({
+            "data": {
+                "id": "1067094924124872705",
+                "text": "Just getting started with Twitter APIs?",
+                "author_id": "2244994945",
+                "attachments": {"media_keys": ["3_1"]},
+            },
+            "matching_rules": [{"id": "1273026480692322304"}],
+        }))
+        .unwrap();
+        let resolved = streamed.resolve();
+        assert_eq!(resolved.author, None);
+        assert_eq!(
+            resolved.unresolved,
+            [
+                Unresolved {
+                    kind: ObjectKind::User,
+                    id: "2244994945",
+                    problem: None,
+                },
+                Unresolved {
+                    kind: ObjectKind::Media,
+                    id: "3_1",
+                    problem: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,763 @@
+//! The v2 filtered stream and its rules.
+//!
+//! Rules are kept on Twitter's side: add and delete them with
+//! [`Twitter::add_stream_rules`] and [`Twitter::delete_stream_rules`], then
+//! open [`Twitter::filtered_stream`] to get the tweets matching any of them.
+//! Or keep the rules in configuration and bring Twitter's in line with
+//! [`Twitter::reconcile_stream_rules`].
+
+use super::api::{Fields, Includes, Problem, Tweet};
+use crate::http::{self, Body, Method, Request};
+use crate::stream::Stream;
+use crate::{Error, Result, Twitter};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::error;
+use std::fmt;
+
+/// The ID Twitter gives a stream rule.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(transparent)]
+pub struct RuleId(pub String);
+
+impl fmt::Display for RuleId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A rule of the filtered stream.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Rule {
+    pub id: RuleId,
+    /// The rule in the v2 operator syntax, like `#rust has:media`.
+    pub value: String,
+    /// A label returned with the tweets matching the rule.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+/// A rule to add, without the ID Twitter will give it.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct NewRule {
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+impl NewRule {
+    pub fn new<S: Into<String>>(value: S) -> NewRule {
+        NewRule {
+            value: value.into(),
+            tag: None,
+        }
+    }
+
+    pub fn tagged<S: Into<String>, T: Into<String>>(value: S, tag: T) -> NewRule {
+        NewRule {
+            value: value.into(),
+            tag: Some(tag.into()),
+        }
+    }
+}
+
+/// Stream rules response
+///
+/// On a dry run, `data` holds the rules that would have been added.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RulesResponse {
+    /// Missing when there are no rules.
+    #[serde(default)]
+    pub data: Vec<Rule>,
+    pub meta: RulesMeta,
+    /// The rules that were not added or deleted, and why.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<Problem>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RulesMeta {
+    /// When the response was sent, like `2020-06-16T22:55:39.356Z`.
+    pub sent: String,
+    /// Only when listing rules.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_count: Option<u32>,
+    /// Only when adding or deleting rules.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<Summary>,
+}
+
+/// Counts of what an add or delete did, or would have done on a dry run.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Summary {
+    pub created: u32,
+    pub not_created: u32,
+    pub valid: u32,
+    pub invalid: u32,
+    pub deleted: u32,
+    pub not_deleted: u32,
+}
+
+/// Filtered stream request
+///
+/// # Resource Information
+///
+/// Response formats: **JSON**
+///
+/// Requires authentication?: **Yes (app only)**
+///
+/// Rate limited?: **Yes**, one standing connection per app
+///
+/// [Docs on Twitter](https://developer.twitter.com/en/docs/twitter-api/tweets/filtered-stream/api-reference/get-tweets-search-stream)
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct FilteredStreamRequest {
+    /// Minutes, up to 5, of tweets missed while disconnected to deliver
+    /// first. Requires Academic Research access.
+    pub backfill_minutes: Option<u8>,
+
+    #[serde(flatten)]
+    pub fields: Fields,
+}
+
+/// A tweet of the filtered stream, with the rules it matched.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct StreamedTweet {
+    pub data: Tweet,
+    /// The objects added by the request's expansions.
+    #[serde(default)]
+    pub includes: Includes,
+    /// Expanded objects that could not be returned.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<Problem>,
+    #[serde(default)]
+    pub matching_rules: Vec<MatchingRule>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MatchingRule {
+    pub id: RuleId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+/// A message from the filtered stream.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FilteredMessage {
+    Tweet(Box<StreamedTweet>),
+    /// Problems with the connection, like an `operational-disconnect`
+    /// before Twitter closes it.
+    Errors(Vec<Problem>),
+}
+
+impl FilteredMessage {
+    /// Parses one message of the filtered stream.
+    pub fn parse(json: &[u8]) -> Result<FilteredMessage> {
+        let mut value: Value = serde_json::from_slice(json)?;
+        let message = if value.get("data").is_none() {
+            FilteredMessage::Errors(serde_json::from_value(value["errors"].take())?)
+        } else {
+            FilteredMessage::Tweet(Box::new(serde_json::from_value(value)?))
+        };
+        Ok(message)
+    }
+}
+
+/// The messages of one filtered stream connection, see [`Stream`].
+#[derive(Debug)]
+pub struct FilteredStream {
+    stream: Stream,
+}
+
+impl Iterator for FilteredStream {
+    type Item = Result<FilteredMessage>;
+
+    fn next(&mut self) -> Option<Result<FilteredMessage>> {
+        match self.stream.next_json() {
+            Ok(Some(json)) => Some(FilteredMessage::parse(&json)),
+            Ok(None) => None,
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+/// The changes turning the current stream rules into the desired ones.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RuleDiff {
+    pub add: Vec<NewRule>,
+    pub delete: Vec<RuleId>,
+}
+
+impl RuleDiff {
+    /// The fewest changes to go from `current` to `desired`.
+    ///
+    /// Rules are the same when both value and tag are. A rule whose tag
+    /// changes is deleted and added again, as Twitter cannot edit rules.
+    /// Twitter allows each value only once, so `desired` giving a value
+    /// under different tags is an error; giving the same rule twice is not.
+    pub fn new(
+        desired: &[NewRule],
+        current: &[Rule],
+    ) -> std::result::Result<RuleDiff, DuplicateValues> {
+        let mut tags = HashMap::new();
+        let mut duplicates = Vec::new();
+        for rule in desired {
+            match tags.insert(rule.value.as_str(), &rule.tag) {
+                Some(tag) if tag != &rule.tag && !duplicates.contains(&rule.value) => {
+                    duplicates.push(rule.value.clone())
+                }
+                _ => {}
+            }
+        }
+        if !duplicates.is_empty() {
+            return Err(DuplicateValues(duplicates));
+        }
+
+        let wanted: HashSet<&NewRule> = desired.iter().collect();
+        let mut kept = HashSet::new();
+        let mut diff = RuleDiff::default();
+        for rule in current {
+            let rule_as_new = NewRule {
+                value: rule.value.clone(),
+                tag: rule.tag.clone(),
+            };
+            if !wanted.contains(&rule_as_new) || !kept.insert(rule_as_new) {
+                diff.delete.push(rule.id.clone());
+            }
+        }
+        for rule in desired {
+            if kept.insert(rule.clone()) {
+                diff.add.push(rule.clone());
+            }
+        }
+        Ok(diff)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.add.is_empty() && self.delete.is_empty()
+    }
+}
+
+/// Rule values given more than once under different tags, see
+/// [`RuleDiff::new`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DuplicateValues(pub Vec<String>);
+
+impl fmt::Display for DuplicateValues {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "stream rule values given under different tags: ")?;
+        for (i, value) in self.0.iter().enumerate() {
+            write!(f, "{}{}", if i == 0 { "" } else { ", " }, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for DuplicateValues {}
+
+/// What [`Twitter::reconcile_stream_rules`] changed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Reconciled {
+    pub added: Vec<Rule>,
+    pub deleted: Vec<RuleId>,
+}
+
+/// Why the stream rules were not reconciled.
+#[derive(Debug)]
+pub enum ReconcileError {
+    /// The desired rules give a value twice, so nothing was changed.
+    Duplicates(DuplicateValues),
+    /// Validating the rules to add found these problems, so nothing was
+    /// changed.
+    Invalid(Vec<Problem>),
+    /// Twitter refused some changes after validation passed; the others
+    /// were made, as `reconciled` lists.
+    Rejected {
+        reconciled: Reconciled,
+        problems: Vec<Problem>,
+    },
+    Api(Error),
+}
+
+impl fmt::Display for ReconcileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (what, problems) = match self {
+            ReconcileError::Duplicates(duplicates) => return duplicates.fmt(f),
+            ReconcileError::Invalid(problems) => ("invalid stream rules", problems),
+            ReconcileError::Rejected { problems, .. } => ("stream rule changes rejected", problems),
+            ReconcileError::Api(error) => return error.fmt(f),
+        };
+        write!(f, "{}", what)?;
+        for (i, problem) in problems.iter().enumerate() {
+            write!(f, "{} {}", if i == 0 { ":" } else { ";" }, problem.title)?;
+            if let Some(value) = &problem.value {
+                write!(f, " ({})", value)?;
+            }
+            for detail in &problem.details {
+                write!(f, ": {}", detail)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ReconcileError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ReconcileError::Duplicates(duplicates) => Some(duplicates),
+            ReconcileError::Api(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<DuplicateValues> for ReconcileError {
+    fn from(duplicates: DuplicateValues) -> ReconcileError {
+        ReconcileError::Duplicates(duplicates)
+    }
+}
+
+impl From<Error> for ReconcileError {
+    fn from(error: Error) -> ReconcileError {
+        ReconcileError::Api(error)
+    }
+}
+
+fn rules_request(body: Value, dry_run: bool) -> Request {
+    let query = if dry_run {
+        vec![("dry_run".to_string(), "true".to_string())]
+    } else {
+        Vec::new()
+    };
+    Request::v2(
+        Method::Post,
+        "tweets/search/stream/rules",
+        query,
+        Body::Json(body),
+    )
+}
+
+impl Twitter {
+    /// Streams tweets matching any of the stream rules.
+    pub fn filtered_stream(&self, request: &FilteredStreamRequest) -> Result<FilteredStream> {
+        let request = Request::v2(
+            Method::Get,
+            "tweets/search/stream",
+            http::params(request),
+            Body::Empty,
+        );
+        Ok(FilteredStream {
+            stream: self.open(&request)?,
+        })
+    }
+
+    /// The stream rules in place.
+    pub fn stream_rules(&self) -> Result<RulesResponse> {
+        self.call(&Request::v2(
+            Method::Get,
+            "tweets/search/stream/rules",
+            Vec::new(),
+            Body::Empty,
+        ))
+    }
+
+    /// Adds stream rules, or only validates them with `dry_run`.
+    ///
+    /// Invalid and duplicate rules are reported in
+    /// [`RulesResponse::errors`] while the others are added.
+    pub fn add_stream_rules(&self, rules: &[NewRule], dry_run: bool) -> Result<RulesResponse> {
+        self.call(&rules_request(json!({ "add": rules }), dry_run))
+    }
+
+    /// Deletes stream rules, or only checks they exist with `dry_run`.
+    pub fn delete_stream_rules(&self, ids: &[RuleId], dry_run: bool) -> Result<RulesResponse> {
+        self.call(&rules_request(json!({ "delete": { "ids": ids } }), dry_run))
+    }
+
+    /// Adds and deletes stream rules until they are `desired`, see
+    /// [`RuleDiff::new`].
+    ///
+    /// New rules are validated with a dry run before anything changes.
+    /// Rules are then deleted before others are added, since Twitter allows
+    /// each value only once.
+    pub fn reconcile_stream_rules(
+        &self,
+        desired: &[NewRule],
+    ) -> std::result::Result<Reconciled, ReconcileError> {
+        let current = self.stream_rules()?.data;
+        let diff = RuleDiff::new(desired, &current)?;
+
+        // Retagged rules would fail validation as duplicates of themselves,
+        // and their values are known to be valid.
+        let values: HashSet<&str> = current.iter().map(|rule| rule.value.as_str()).collect();
+        let unknown: Vec<_> = diff
+            .add
+            .iter()
+            .filter(|rule| !values.contains(rule.value.as_str()))
+            .cloned()
+            .collect();
+        if !unknown.is_empty() {
+            let validated = self.add_stream_rules(&unknown, true)?;
+            if !validated.errors.is_empty() {
+                return Err(ReconcileError::Invalid(validated.errors));
+            }
+        }
+
+        let mut problems = Vec::new();
+        let mut deleted = diff.delete;
+        if !deleted.is_empty() {
+            problems = self.delete_stream_rules(&deleted, false)?.errors;
+            // Twitter names the rules it did not delete by ID.
+            deleted.retain(|id| {
+                !problems.iter().any(|problem| {
+                    problem.id.as_ref() == Some(&id.0) || problem.value.as_ref() == Some(&id.0)
+                })
+            });
+        }
+        let mut added = Vec::new();
+        if !diff.add.is_empty() {
+            let response = self.add_stream_rules(&diff.add, false)?;
+            added = response.data;
+            problems.extend(response.errors);
+        }
+        let reconciled = Reconciled { added, deleted };
+        if !problems.is_empty() {
+            return Err(ReconcileError::Rejected {
+                reconciled,
+                problems,
+            });
+        }
+        Ok(reconciled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock::Mock;
+    use crate::v2::api::Expansion;
+
+    #[test]
+    fn manages_rules() {
+        let mock = Mock::default();
+        mock.respond(
+            200,
+            r#"{"data":[{"id":"1273026480692322304","value":"cat has:media","tag":"cats with media"}],"meta":{"sent":"2020-06-16T22:55:39.356Z","result_count":1}}"#,
+        )
+        .respond(
+            200,
+            r#"{"data":[{"value":"dog has:images","id":"1273028376882589696"}],"meta":{"sent":"2020-06-16T23:03:00.015Z","summary":{"created":1,"not_created":1,"valid":1,"invalid":1}},"errors":[{"value":"cat has:nothing","details":["Reference to invalid operator 'has:nothing'."],"title":"Invalid Rule","type":"https://api.twitter.com/2/problems/invalid-rules"}]}"#,
+        )
+        .respond(
+            200,
+            r#"{"meta":{"sent":"2020-06-16T23:05:00.015Z","summary":{"deleted":1,"not_deleted":0}}}"#,
+        );
+        let twitter = Twitter::new(mock.clone());
+
+        let rules = twitter.stream_rules().unwrap();
+        assert_eq!(
+            rules.data,
+            vec![Rule {
+                id: RuleId("1273026480692322304".to_string()),
+                value: "cat has:media".to_string(),
+                tag: Some("cats with media".to_string()),
+            }]
+        );
+        assert_eq!(rules.meta.result_count, Some(1));
+
+        let added = twitter
+            .add_stream_rules(
+                &[
+                    NewRule::new("dog has:images"),
+                    NewRule::tagged("cat has:nothing", "cats"),
+                ],
+                true,
+            )
+            .unwrap();
+        let summary = added.meta.summary.unwrap();
+        assert_eq!((summary.valid, summary.invalid), (1, 1));
+        assert_eq!(added.errors[0].value.as_deref(), Some("cat has:nothing"));
+        assert_eq!(
+            added.errors[0].details,
+            ["Reference to invalid operator 'has:nothing'."]
+        );
+
+        let deleted = twitter
+            .delete_stream_rules(&[rules.data[0].id.clone()], false)
+            .unwrap();
+        assert_eq!(deleted.data, []);
+        assert_eq!(deleted.meta.summary.unwrap().deleted, 1);
+
+        let sent = mock.requests();
+        assert_eq!(sent[0].method, Method::Get);
+        assert_eq!(
+            sent[0].url,
+            "https://api.twitter.com/2/tweets/search/stream/rules"
+        );
+        assert_eq!(sent[1].query, [("dry_run".to_string(), "true".to_string())]);
+        assert_eq!(
+            sent[1].body,
+            Body::Json(json!({"add": [
+                {"value": "dog has:images"},
+                {"value": "cat has:nothing", "tag": "cats"},
+            ]}))
+        );
+        assert_eq!(sent[2].query, []);
+        assert_eq!(
+            sent[2].body,
+            Body::Json(json!({"delete": {"ids": ["1273026480692322304"]}}))
+        );
+    }
+
+    #[test]
+    fn streams_matching_tweets() {
+        let body = [
+            r#"{"data":{"id":"1067094924124872705","text":"Just getting started with Twitter APIs? Find out what you need","author_id":"2244994945"},"includes":{"users":[{"id":"2244994945","name":"Twitter Dev","username":"TwitterDev"}]},"matching_rules":[{"id":"1273026480692322304","tag":"cats with media"},{"id":"1273028376882589696"}]}"#,
+            "",
+            r#"{"errors":[{"title":"operational-disconnect","disconnect_type":"UpstreamOperationalDisconnect","detail":"This stream has been disconnected upstream for operational reasons.","type":"https://api.twitter.com/2/problems/operational-disconnect"}]}"#,
+            "",
+        ]
+        .join("\r\n");
+        let mock = Mock::default();
+        mock.stream(200, &body);
+        let twitter = Twitter::new(mock.clone());
+
+        let request = FilteredStreamRequest {
+            backfill_minutes: Some(2),
+            fields: Fields {
+                expansions: Some(vec![Expansion::AuthorId]),
+                ..Fields::default()
+            },
+        };
+        let messages = twitter
+            .filtered_stream(&request)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        match &messages[0] {
+            FilteredMessage::Tweet(tweet) => {
+                assert_eq!(tweet.data.id, "1067094924124872705");
+                assert_eq!(tweet.includes.users[0].username, "TwitterDev");
+                assert_eq!(
+                    tweet.matching_rules[1],
+                    MatchingRule {
+                        id: RuleId("1273028376882589696".to_string()),
+                        tag: None,
+                    }
+                );
+            }
+            message => panic!("unexpected {:?}", message),
+        }
+        match &messages[1] {
+            FilteredMessage::Errors(errors) => {
+                assert_eq!(errors[0].title, "operational-disconnect")
+            }
+            message => panic!("unexpected {:?}", message),
+        }
+        assert_eq!(messages.len(), 2);
+
+        let sent = &mock.requests()[0];
+        assert_eq!(sent.url, "https://api.twitter.com/2/tweets/search/stream");
+        assert_eq!(
+            sent.query,
+            [
+                ("backfill_minutes".to_string(), "2".to_string()),
+                ("expansions".to_string(), "author_id".to_string()),
+            ]
+        );
+    }
+
+    fn rule(id: &str, value: &str, tag: Option<&str>) -> Rule {
+        Rule {
+            id: RuleId(id.to_string()),
+            value: value.to_string(),
+            tag: tag.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn diffs_rules() {
+        let current = [
+            rule("1", "cat has:media", Some("cats")),
+            rule("2", "dog", Some("dogs")),
+            rule("3", "bird", None),
+            rule("4", "cat has:media", Some("cats")),
+        ];
+        let desired = [
+            NewRule::tagged("cat has:media", "cats"),
+            NewRule::tagged("dog", "puppies"),
+            NewRule::new("fish has:images"),
+            NewRule::new("fish has:images"),
+        ];
+        let diff = RuleDiff::new(&desired, &current).unwrap();
+        assert_eq!(
+            diff.delete,
+            [
+                RuleId("2".to_string()),
+                RuleId("3".to_string()),
+                RuleId("4".to_string())
+            ]
+        );
+        assert_eq!(
+            diff.add,
+            [
+                NewRule::tagged("dog", "puppies"),
+                NewRule::new("fish has:images"),
+            ]
+        );
+        assert!(RuleDiff::new(&desired[..1], &current[..1])
+            .unwrap()
+            .is_empty());
+
+        let duplicated = [
+            NewRule::tagged("dog", "dogs"),
+            NewRule::new("bird"),
+            NewRule::tagged("dog", "puppies"),
+            NewRule::tagged("bird", "birds"),
+            NewRule::new("dog"),
+        ];
+        let duplicates = RuleDiff::new(&duplicated, &current).unwrap_err();
+        assert_eq!(
+            duplicates,
+            DuplicateValues(vec!["dog".to_string(), "bird".to_string()])
+        );
+        assert_eq!(
+            duplicates.to_string(),
+            "stream rule values given under different tags: dog, bird"
+        );
+    }
+
+    #[test]
+    fn reconciles_rules() {
+        let rules = r#"{"data":[{"id":"1","value":"cat has:media","tag":"cats"},{"id":"2","value":"dog","tag":"dogs"},{"id":"3","value":"bird"}],"meta":{"sent":"2020-06-16T22:55:39.356Z","result_count":3}}"#;
+        let mock = Mock::default();
+        mock.respond(200, rules)
+            .respond(
+                200,
+                r#"{"data":[{"value":"fish has:images","id":"5"}],"meta":{"sent":"2020-06-16T23:00:00.000Z","summary":{"created":0,"not_created":0,"valid":1,"invalid":0}}}"#,
+            )
+            .respond(
+                200,
+                r#"{"meta":{"sent":"2020-06-16T23:00:01.000Z","summary":{"deleted":2,"not_deleted":0}}}"#,
+            )
+            .respond(
+                200,
+                r#"{"data":[{"value":"dog","tag":"puppies","id":"6"},{"value":"fish has:images","id":"7"}],"meta":{"sent":"2020-06-16T23:00:02.000Z","summary":{"created":2,"not_created":0,"valid":2,"invalid":0}}}"#,
+            )
+            .respond(200, rules)
+            .respond(
+                200,
+                r#"{"meta":{"sent":"2020-06-16T23:01:00.000Z","summary":{"created":0,"not_created":1,"valid":0,"invalid":1}},"errors":[{"value":"fish has:nothing","details":["Reference to invalid operator 'has:nothing'."],"title":"Invalid Rule","type":"https://api.twitter.com/2/problems/invalid-rules"}]}"#,
+            );
+        let twitter = Twitter::new(mock.clone());
+
+        let reconciled = twitter
+            .reconcile_stream_rules(&[
+                NewRule::tagged("cat has:media", "cats"),
+                NewRule::tagged("dog", "puppies"),
+                NewRule::new("fish has:images"),
+            ])
+            .unwrap();
+        assert_eq!(
+            reconciled,
+            Reconciled {
+                added: vec![
+                    rule("6", "dog", Some("puppies")),
+                    rule("7", "fish has:images", None)
+                ],
+                deleted: vec![RuleId("2".to_string()), RuleId("3".to_string())],
+            }
+        );
+
+        let sent = mock.requests();
+        assert_eq!(
+            sent[1].body,
+            Body::Json(json!({"add": [{"value": "fish has:images"}]}))
+        );
+        assert_eq!(sent[1].query, [("dry_run".to_string(), "true".to_string())]);
+        assert_eq!(
+            sent[2].body,
+            Body::Json(json!({"delete": {"ids": ["2", "3"]}}))
+        );
+        assert_eq!(sent[3].query, []);
+        assert_eq!(
+            sent[3].body,
+            Body::Json(json!({"add": [
+                {"value": "dog", "tag": "puppies"},
+                {"value": "fish has:images"},
+            ]}))
+        );
+
+        let error = twitter
+            .reconcile_stream_rules(&[NewRule::new("fish has:nothing")])
+            .unwrap_err();
+        match &error {
+            ReconcileError::Invalid(problems) => {
+                assert_eq!(problems[0].value.as_deref(), Some("fish has:nothing"))
+            }
+            error => panic!("unexpected {:?}", error),
+        }
+        assert_eq!(
+            error.to_string(),
+            "invalid stream rules: Invalid Rule (fish has:nothing): Reference to invalid operator 'has:nothing'."
+        );
+        assert_eq!(mock.requests().len(), 6);
+    }
+
+    #[test]
+    fn reports_partial_reconciliation() {
+        let rules = r#"{"data":[{"id":"1","value":"cat has:media","tag":"cats"},{"id":"2","value":"dog","tag":"dogs"},{"id":"3","value":"bird"}],"meta":{"sent":"2020-06-16T22:55:39.356Z","result_count":3}}"#;
+        let mock = Mock::default();
+        mock.respond(200, rules)
+            .respond(
+                200,
+                r#"{"data":[{"value":"fish has:images","id":"5"}],"meta":{"sent":"2020-06-16T23:00:00.000Z","summary":{"created":0,"not_created":0,"valid":1,"invalid":0}}}"#,
+            )
+            .respond(
+                200,
+                r#"{"meta":{"sent":"2020-06-16T23:00:01.000Z","summary":{"deleted":1,"not_deleted":1}},"errors":[{"id":"3","title":"Not Found","type":"https://api.twitter.com/2/problems/resource-not-found"}]}"#,
+            )
+            .respond(
+                200,
+                r#"{"data":[{"value":"dog","tag":"puppies","id":"6"}],"meta":{"sent":"2020-06-16T23:00:02.000Z","summary":{"created":1,"not_created":1,"valid":2,"invalid":0}},"errors":[{"value":"fish has:images","id":"9","title":"DuplicateRule","type":"https://api.twitter.com/2/problems/duplicate-rules"}]}"#,
+            )
+            .respond(200, rules);
+        let twitter = Twitter::new(mock.clone());
+
+        let error = twitter
+            .reconcile_stream_rules(&[
+                NewRule::tagged("cat has:media", "cats"),
+                NewRule::tagged("dog", "puppies"),
+                NewRule::new("fish has:images"),
+            ])
+            .unwrap_err();
+        match error {
+            ReconcileError::Rejected {
+                reconciled,
+                problems,
+            } => {
+                assert_eq!(
+                    reconciled,
+                    Reconciled {
+                        added: vec![rule("6", "dog", Some("puppies"))],
+                        deleted: vec![RuleId("2".to_string())],
+                    }
+                );
+                let titles: Vec<_> = problems.iter().map(|problem| &problem.title).collect();
+                assert_eq!(titles, ["Not Found", "DuplicateRule"]);
+            }
+            error => panic!("unexpected {:?}", error),
+        }
+
+        // Duplicate values are caught before anything is sent to change
+        // the rules.
+        let error = twitter
+            .reconcile_stream_rules(&[NewRule::new("dog"), NewRule::tagged("dog", "puppies")])
+            .unwrap_err();
+        match error {
+            ReconcileError::Duplicates(DuplicateValues(values)) => assert_eq!(values, ["dog"]),
+            error => panic!("unexpected {:?}", error),
+        }
+        assert_eq!(mock.requests().len(), 5);
+    }
+}